@@ -0,0 +1,66 @@
+use std::hint::black_box;
+
+use clap::ValueEnum;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use h4_bigdata::{clock::FixedClock, format::Format, Message};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// The seed of the benchmarked message, fixed so that every run encodes the same values.
+const SEED: u64 = 42;
+
+/// The timestamp of the benchmarked message, fixed like its other values.
+const TIMESTAMP: u128 = 1_700_000_000_000;
+
+/// Generate the message every format is benchmarked with.
+fn message() -> Message {
+    Message::with_rng(&mut StdRng::seed_from_u64(SEED), &FixedClock(TIMESTAMP), 0)
+        .expect("Fixed clock should tell the time!")
+}
+
+/// Get the name a format is selected by on the command line.
+fn name(format: Format) -> String {
+    format
+        .to_possible_value()
+        .map_or_else(|| format!("{format:?}"), |x| x.get_name().to_string())
+}
+
+fn serialize(c: &mut Criterion) {
+    let message = message();
+
+    let mut group = c.benchmark_group("serialize");
+    for format in Format::value_variants() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name(*format)),
+            format,
+            |b, x| {
+                b.iter(|| x.encode(black_box(&message)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn deserialize(c: &mut Criterion) {
+    let message = message();
+
+    let mut group = c.benchmark_group("deserialize");
+    for format in Format::value_variants() {
+        let bytes = format
+            .encode(&message)
+            .expect("Failed to encode the message!");
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name(*format)),
+            format,
+            |b, x| {
+                b.iter(|| x.decode(black_box(&bytes)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, serialize, deserialize);
+criterion_main!(benches);
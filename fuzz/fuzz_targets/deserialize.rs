@@ -0,0 +1,19 @@
+#![no_main]
+
+use clap::ValueEnum;
+use h4_bigdata::{envelope::Envelope, format::Format, Message};
+use libfuzzer_sys::fuzz_target;
+
+// Every decoder of untrusted bytes must return an error on malformed input, never panic.
+fuzz_target!(|bytes: &[u8]| {
+    let _ = serde_json::from_slice::<Message>(bytes);
+    let _ = Envelope::<Message>::decode(bytes);
+
+    for format in Format::value_variants() {
+        let _ = format.decode(bytes);
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        let _ = text.parse::<Message>();
+    }
+});
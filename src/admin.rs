@@ -0,0 +1,65 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, Result};
+use rdkafka::{
+    admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
+    client::DefaultClientContext,
+    types::RDKafkaErrorCode,
+    ClientConfig,
+};
+use tracing::info;
+
+/// The number of partitions or replicas requesting the default of the brokers.
+const BROKER_DEFAULT: i32 = -1;
+
+/// Create topics in the cluster, skipping those that already exist.
+///
+/// # Arguments
+///
+/// * `bootstrap_server` - A comma-separated list of brokers to connect to.
+/// * `overrides` - Extra [client properties](https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md).
+/// * `topics` - The names of the topics to create.
+/// * `partitions` - The number of partitions of each topic, the broker default if `None`.
+/// * `replication_factor` - The number of replicas of each partition, the broker default if
+///   `None`.
+/// * `timeout` - The maximum time to wait for the brokers to create the topics.
+///
+/// # Errors
+///
+/// * If the admin client couldn't be created.
+/// * If the brokers refused to create a topic.
+pub async fn create_topics(
+    bootstrap_server: &str,
+    overrides: &HashMap<String, String>,
+    topics: &[String],
+    partitions: Option<i32>,
+    replication_factor: Option<i32>,
+    timeout: Duration,
+) -> Result<()> {
+    let mut config = ClientConfig::new();
+    config.set("bootstrap.servers", bootstrap_server);
+    for (key, value) in overrides {
+        config.set(key, value);
+    }
+
+    let admin: AdminClient<DefaultClientContext> = config.create()?;
+    let partitions = partitions.unwrap_or(BROKER_DEFAULT);
+    let replication_factor = replication_factor.unwrap_or(BROKER_DEFAULT);
+    let new_topics: Vec<_> = topics
+        .iter()
+        .map(|x| NewTopic::new(x, partitions, TopicReplication::Fixed(replication_factor)))
+        .collect();
+
+    let options = AdminOptions::new().operation_timeout(Some(timeout));
+    for result in admin.create_topics(&new_topics, &options).await? {
+        match result {
+            Ok(topic) => info!("Created topic {topic}."),
+            Err((topic, RDKafkaErrorCode::TopicAlreadyExists)) => {
+                info!("Topic {topic} already exists.");
+            }
+            Err((topic, code)) => bail!("Failed to create topic {topic}: {code}!"),
+        }
+    }
+
+    Ok(())
+}
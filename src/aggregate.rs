@@ -0,0 +1,329 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use crate::{Message, MilliwattHours};
+
+/// Order customer totals from the highest to the lowest.
+fn descending(a: &(u32, MilliwattHours), b: &(u32, MilliwattHours)) -> Ordering {
+    b.1.value().total_cmp(&a.1.value())
+}
+
+/// Running totals of the consumption of every customer.
+///
+/// # Fields
+///
+/// * `totals` - The total consumption of each customer, by customer ID.
+/// * `count` - The number of messages recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Aggregator {
+    totals: HashMap<u32, MilliwattHours>,
+    count: u64,
+}
+
+impl Aggregator {
+    /// Add the consumption of a message to the total of its customer.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to record.
+    pub fn record(&mut self, message: &Message) {
+        let total = self
+            .totals
+            .entry(message.customer_id())
+            .or_insert(MilliwattHours(0.0));
+        *total = total.saturating_add(message.consumption());
+        self.count += 1;
+    }
+
+    /// Get the customers with the highest total consumption.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The largest number of customers to return.
+    ///
+    /// # Returns
+    ///
+    /// * Up to `n` customer IDs and their totals, sorted by descending total.
+    #[must_use]
+    pub fn top(&self, n: usize) -> Vec<(u32, MilliwattHours)> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let mut totals: Vec<_> = self
+            .totals
+            .iter()
+            .map(|(id, total)| (*id, *total))
+            .collect();
+
+        // Only the top rows are sorted, so that reports stay cheap with many customers.
+        if totals.len() > n {
+            totals.select_nth_unstable_by(n - 1, descending);
+            totals.truncate(n);
+        }
+
+        totals.sort_unstable_by(descending);
+
+        totals
+    }
+
+    /// Get the number of distinct customers recorded.
+    ///
+    /// # Returns
+    ///
+    /// * The number of customers.
+    #[must_use]
+    pub fn customers(&self) -> usize {
+        self.totals.len()
+    }
+
+    /// Get the number of messages recorded.
+    ///
+    /// # Returns
+    ///
+    /// * The number of messages.
+    #[must_use]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Sums of the consumption of every customer over fixed windows of time, e.g. every minute.
+///
+/// Windows are closed by the timestamps of the messages rather than the clock, once a message
+/// is recorded at least a `grace` period after their end. Until then, late messages are still
+/// added to them, while messages arriving after that are dropped.
+///
+/// # Fields
+///
+/// * `window` - The length of each window, in milliseconds.
+/// * `grace` - How long after its end a window still accepts late messages, in milliseconds.
+/// * `sums` - The consumption of each open window, by its start and customer ID.
+/// * `watermark` - The latest timestamp recorded.
+/// * `late` - The number of messages dropped for arriving after their window closed.
+#[derive(Debug, Clone)]
+pub struct WindowedAggregator {
+    window: u128,
+    grace: u128,
+    sums: BTreeMap<(u128, u32), MilliwattHours>,
+    watermark: u128,
+    late: u64,
+}
+
+impl WindowedAggregator {
+    /// Construct a new `WindowedAggregator` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The length of each window.
+    /// * `grace` - How long after its end a window still accepts late messages.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `WindowedAggregator`.
+    ///
+    /// # Panics
+    ///
+    /// * If the `window` is shorter than a millisecond.
+    #[must_use]
+    pub fn new(window: Duration, grace: Duration) -> Self {
+        assert!(
+            window.as_millis() > 0,
+            "Window must be at least a millisecond!"
+        );
+
+        Self {
+            window: window.as_millis(),
+            grace: grace.as_millis(),
+            sums: BTreeMap::new(),
+            watermark: 0,
+            late: 0,
+        }
+    }
+
+    /// Add the consumption of a message to the sum of its customer in its window.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to record.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the message was recorded, `false` if its window was already closed.
+    pub fn record(&mut self, message: &Message) -> bool {
+        let start = self.window_start(message.timestamp());
+        if self.is_closed(start) {
+            self.late += 1;
+
+            return false;
+        }
+
+        let sum = self
+            .sums
+            .entry((start, message.customer_id()))
+            .or_insert(MilliwattHours(0.0));
+        *sum = sum.saturating_add(message.consumption());
+        self.watermark = self.watermark.max(message.timestamp());
+
+        true
+    }
+
+    /// Remove the windows that are closed.
+    ///
+    /// # Returns
+    ///
+    /// * The sum of every customer in each closed window, as a message timestamped with the start
+    ///   of the window, ordered by window and customer ID.
+    pub fn flush(&mut self) -> Vec<Message> {
+        let Some(cutoff) = self.watermark.checked_sub(self.window + self.grace) else {
+            return Vec::new();
+        };
+
+        let open = self.sums.split_off(&(cutoff + 1, 0));
+
+        into_messages(std::mem::replace(&mut self.sums, open))
+    }
+
+    /// Remove every window, whether it's closed or not, e.g. when shutting down.
+    ///
+    /// The sums of the windows that are still open are partial, so if more of their messages are
+    /// consumed later, e.g. after a restart, downstream sees more than one sum for those windows.
+    ///
+    /// # Returns
+    ///
+    /// * The sum of every customer in each window, see [`WindowedAggregator::flush`].
+    pub fn drain(&mut self) -> Vec<Message> {
+        into_messages(std::mem::take(&mut self.sums))
+    }
+
+    /// Get the number of messages dropped for arriving after their window closed.
+    ///
+    /// # Returns
+    ///
+    /// * The number of late messages.
+    #[must_use]
+    pub const fn late(&self) -> u64 {
+        self.late
+    }
+
+    /// Get the start of the window a timestamp falls in.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The timestamp, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * The start of the window, in milliseconds, as the timestamp of its sums.
+    #[must_use]
+    pub const fn window_start(&self, timestamp: u128) -> u128 {
+        timestamp - timestamp % self.window
+    }
+
+    /// Check whether a window is closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The start of the window, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the window ended at least a grace period before the watermark.
+    const fn is_closed(&self, start: u128) -> bool {
+        start + self.window + self.grace <= self.watermark
+    }
+}
+
+/// Turn the sums of windows into messages.
+///
+/// # Arguments
+///
+/// * `sums` - The consumption of each window, by its start and customer ID.
+///
+/// # Returns
+///
+/// * A message per sum, timestamped with the start of its window.
+fn into_messages(sums: BTreeMap<(u128, u32), MilliwattHours>) -> Vec<Message> {
+    sums.into_iter()
+        .map(|((start, customer_id), sum)| Message::new(customer_id, sum, start))
+        .collect()
+}
+
+/// The offsets of consumed records, held back from being committed until the sums of the windows
+/// they were recorded in are delivered, so that no record is lost if the consumer stops first.
+///
+/// # Fields
+///
+/// * `windows` - The lowest offset recorded in each open window, by its start and partition.
+/// * `next` - The offset after the last consumed record, by partition.
+/// * `committed` - The last offset returned to be committed, by partition.
+#[derive(Debug, Clone, Default)]
+pub struct PendingOffsets {
+    windows: BTreeMap<u128, HashMap<i32, i64>>,
+    next: HashMap<i32, i64>,
+    committed: HashMap<i32, i64>,
+}
+
+impl PendingOffsets {
+    /// Record a consumed record.
+    ///
+    /// # Arguments
+    ///
+    /// * `partition` - The partition of the record.
+    /// * `offset` - The offset of the record.
+    /// * `window` - The start of the window the record was summed in, `None` if it's done with,
+    ///   e.g. because it was skipped.
+    pub fn record(&mut self, partition: i32, offset: i64, window: Option<u128>) {
+        if let Some(start) = window {
+            let lowest = self
+                .windows
+                .entry(start)
+                .or_default()
+                .entry(partition)
+                .or_insert(offset);
+            *lowest = (*lowest).min(offset);
+        }
+
+        let next = self.next.entry(partition).or_insert(offset + 1);
+        *next = (*next).max(offset + 1);
+    }
+
+    /// Release the records of a window once its sums are delivered.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The start of the window.
+    pub fn deliver(&mut self, start: u128) {
+        self.windows.remove(&start);
+    }
+
+    /// Take the offsets that can be committed, up to the lowest offset still awaiting the
+    /// delivery of its window in each partition.
+    ///
+    /// # Returns
+    ///
+    /// * The offset to commit for each partition it advanced in since the last call, ordered by
+    ///   partition.
+    pub fn committable(&mut self) -> Vec<(i32, i64)> {
+        let mut offsets = Vec::new();
+        for (&partition, &next) in &self.next {
+            let offset = self
+                .windows
+                .values()
+                .filter_map(|x| x.get(&partition))
+                .min()
+                .map_or(next, |&x| x);
+            if self.committed.get(&partition).is_none_or(|&x| x < offset) {
+                self.committed.insert(partition, offset);
+                offsets.push((partition, offset));
+            }
+        }
+
+        offsets.sort_unstable();
+
+        offsets
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    str,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use h4_bigdata::{
+    aggregate::{Aggregator, PendingOffsets, WindowedAggregator},
+    compression::{Compression, CONTENT_ENCODING_HEADER},
+    config::{CommitMode, ConsumerConfig},
+    envelope::CURRENT_VERSION,
+    format::{Format, CONTENT_TYPE_HEADER, SCHEMA_VERSION_HEADER},
+    producer::{self, MessageProducer},
+    shutdown,
+    timestamp::{TimestampUnit, TIMESTAMP_UNIT_HEADER},
+    Message,
+};
+use rdkafka::{
+    consumer::{self, Consumer, StreamConsumer},
+    message::{BorrowedMessage, Headers},
+    ClientConfig, Message as _, Offset, TopicPartitionList,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
+    let config = ConsumerConfig::from_args();
+    let consumer = create_consumer(
+        &config.brokers.join(","),
+        &config.group_id,
+        config.commit_mode,
+    )?;
+    consumer.subscribe(&[config.topic.as_str()])?;
+
+    info!("Subscribed to topic: {}", config.topic);
+    let mut aggregator = Aggregator::default();
+    let mut windowed = match &config.output_topic {
+        Some(topic) => {
+            info!(
+                "Producing the consumption over every {}s window to topic: {topic}",
+                config.window,
+            );
+
+            Some((
+                MessageProducer::new(&config.brokers, topic, &HashMap::new())?,
+                WindowedAggregator::new(
+                    Duration::from_secs(config.window),
+                    Duration::from_secs(config.grace),
+                ),
+            ))
+        }
+        None => None,
+    };
+    let retry = Retry {
+        max_retries: config.max_retries,
+        base_delay: Duration::from_millis(config.retry_base_delay),
+        cancel: CancellationToken::new(),
+    };
+    tokio::spawn({
+        let cancel = retry.cancel.clone();
+        async move {
+            shutdown::wait_for_signal().await;
+            cancel.cancel();
+        }
+    });
+
+    let mut offsets = PendingOffsets::default();
+    let mut unsent = Vec::new();
+    loop {
+        let record = tokio::select! {
+            record = consumer.recv() => record,
+            () = retry.cancel.cancelled() => break,
+        };
+        let record = match record {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Kafka Error: {e}");
+                continue;
+            }
+        };
+
+        let window = match read(&record) {
+            Some(message) => {
+                debug!(
+                    "Consumed Message: customer_id={}, consumption={}, timestamp={}",
+                    message.customer_id(),
+                    message.consumption().value(),
+                    message.timestamp(),
+                );
+
+                aggregator.record(&message);
+                if config.report_every > 0 && aggregator.count() % config.report_every == 0 {
+                    report(&aggregator, config.top);
+                }
+
+                windowed.as_mut().and_then(|(_, windows)| {
+                    if windows.record(&message) {
+                        Some(windows.window_start(message.timestamp()))
+                    } else {
+                        warn!(
+                            "Dropping late message at offset {}, its window is closed.",
+                            record.offset(),
+                        );
+
+                        None
+                    }
+                })
+            }
+            None => None,
+        };
+        offsets.record(record.partition(), record.offset(), window);
+
+        if let Some((producer, windows)) = &mut windowed {
+            let sums = windows.flush();
+            if !sums.is_empty() {
+                produce_sums(producer, sums, &mut unsent, &mut offsets, &retry).await;
+            }
+        }
+
+        if config.commit_mode == CommitMode::Manual {
+            commit(
+                &consumer,
+                &config.topic,
+                &mut offsets,
+                consumer::CommitMode::Async,
+            );
+        }
+    }
+
+    info!("Shutting down...");
+    if let Some((producer, windows)) = &mut windowed {
+        // With manual commits, the records of the open windows are consumed again after a
+        // restart, so only the closed windows are produced to not send partial sums.
+        let sums = match config.commit_mode {
+            CommitMode::Manual => Vec::new(),
+            CommitMode::Auto => windows.drain(),
+        };
+        if !sums.is_empty() || !unsent.is_empty() {
+            produce_sums(producer, sums, &mut unsent, &mut offsets, &retry).await;
+        }
+    }
+
+    if config.commit_mode == CommitMode::Manual {
+        commit(
+            &consumer,
+            &config.topic,
+            &mut offsets,
+            consumer::CommitMode::Sync,
+        );
+    }
+
+    Ok(())
+}
+
+/// Read the message of a record, logging why it's skipped if it holds none.
+///
+/// # Arguments
+///
+/// * `record` - The record to read.
+///
+/// # Returns
+///
+/// * The message of the record, `None` if it's a tombstone or couldn't be decoded.
+fn read(record: &BorrowedMessage<'_>) -> Option<Message> {
+    let Some(payload) = record.payload() else {
+        // Tombstones only delete their customer from compacted topics, holding no message.
+        debug!(
+            "Skipping tombstone or empty payload at offset {}",
+            record.offset()
+        );
+        return None;
+    };
+
+    let payload = match decompress(record, payload) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!(
+                "Skipping undecodable payload at offset {}: {e:#}",
+                record.offset()
+            );
+            return None;
+        }
+    };
+
+    match decode(record, &payload) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            warn!(
+                "Skipping malformed payload at offset {}: {e:#}",
+                record.offset()
+            );
+
+            None
+        }
+    }
+}
+
+/// Commit the offsets of the records that are done with, see [`PendingOffsets::committable`].
+///
+/// Failing to commit only means the records may be processed again after a restart, so errors
+/// are logged rather than returned.
+///
+/// # Arguments
+///
+/// * `consumer` - The consumer to commit the offsets of.
+/// * `topic` - The topic the records were consumed from.
+/// * `offsets` - The offsets of the consumed records.
+/// * `mode` - Whether to wait for the commit to complete.
+fn commit(
+    consumer: &StreamConsumer,
+    topic: &str,
+    offsets: &mut PendingOffsets,
+    mode: consumer::CommitMode,
+) {
+    let committable = offsets.committable();
+    if committable.is_empty() {
+        return;
+    }
+
+    let mut list = TopicPartitionList::new();
+    for (partition, offset) in committable {
+        if let Err(e) = list.add_partition_offset(topic, partition, Offset::Offset(offset)) {
+            warn!("Failed to commit offset {offset} of partition {partition}: {e}");
+        }
+    }
+
+    if let Err(e) = consumer.commit(&list, mode) {
+        warn!("Failed to commit offsets: {e}");
+    }
+}
+
+/// Log a table of the customers with the highest total consumption.
+///
+/// # Arguments
+///
+/// * `aggregator` - The totals to report.
+/// * `top` - The number of customers to report.
+fn report(aggregator: &Aggregator, top: usize) {
+    let mut table = format!(
+        "Top {top} of {} customers after {} messages:",
+        aggregator.customers(),
+        aggregator.count(),
+    );
+    for (rank, (customer_id, total)) in aggregator.top(top).into_iter().enumerate() {
+        let _ = write!(
+            table,
+            "\n{:>4}. {customer_id:>10} {:>16.2} mWh",
+            rank + 1,
+            total.value()
+        );
+    }
+
+    info!("{table}");
+}
+
+/// How to retry the window sums that failed to be delivered.
+///
+/// # Fields
+///
+/// * `max_retries` - The most times to retry a sum failing with a retryable error.
+/// * `base_delay` - The time to wait before the first retry, doubled on every retry.
+/// * `cancel` - The token to stop retrying on, cancelled when shutting down.
+struct Retry {
+    max_retries: u32,
+    base_delay: Duration,
+    cancel: CancellationToken,
+}
+
+/// Produce the sums of closed windows, along with the ones that failed to be delivered before.
+///
+/// Failed sums are retried with backoff, see [`producer::retry_failed`], and the ones that still
+/// fail are kept in `unsent` to be produced again on the next flush. Only the windows whose every
+/// sum was delivered have their records released to be committed, so that the records of the
+/// others are consumed again after a restart.
+///
+/// # Arguments
+///
+/// * `producer` - The producer to produce the sums with.
+/// * `sums` - The sums to produce, see [`WindowedAggregator::flush`].
+/// * `unsent` - The sums that failed to be delivered, replaced by the ones that still fail.
+/// * `offsets` - The offsets of the consumed records, released once their window is delivered.
+/// * `retry` - How to retry the sums that fail.
+async fn produce_sums(
+    producer: &MessageProducer,
+    mut sums: Vec<Message>,
+    unsent: &mut Vec<Message>,
+    offsets: &mut PendingOffsets,
+    retry: &Retry,
+) {
+    sums.splice(0..0, unsent.drain(..));
+
+    let results = producer.send_batch(&sums).await;
+    for (sum, result) in sums.iter().zip(results) {
+        let result = producer::retry_failed(
+            producer,
+            sum,
+            result,
+            retry.max_retries,
+            retry.base_delay,
+            &retry.cancel,
+        )
+        .await;
+        if let Err(e) = result {
+            error!("Failed to produce a window sum: {e}");
+            unsent.push(sum.clone());
+        }
+    }
+
+    let failed = unsent
+        .iter()
+        .map(Message::timestamp)
+        .collect::<BTreeSet<_>>();
+    for start in sums.iter().map(Message::timestamp) {
+        if !failed.contains(&start) {
+            offsets.deliver(start);
+        }
+    }
+
+    if failed.is_empty() {
+        info!("Produced {} window sums.", sums.len());
+    } else {
+        warn!(
+            "Failed to produce the sums of {} windows, keeping them for the next flush.",
+            failed.len(),
+        );
+    }
+}
+
+/// Get the value of a header of a record.
+///
+/// # Arguments
+///
+/// * `record` - The record holding the header.
+/// * `key` - The key of the header.
+///
+/// # Returns
+///
+/// * The value of the first header with the `key`, `None` if there's none.
+fn header<'a>(record: &'a BorrowedMessage<'_>, key: &str) -> Option<&'a [u8]> {
+    record
+        .headers()
+        .and_then(|headers| headers.iter().find(|x| x.key == key).and_then(|x| x.value))
+}
+
+/// Decode the payload of a record, in the format named by its [`CONTENT_TYPE_HEADER`].
+///
+/// Records without the header are decoded as JSON, as produced before it was introduced. The
+/// timestamp is converted to milliseconds from the unit named by its [`TIMESTAMP_UNIT_HEADER`].
+///
+/// # Arguments
+///
+/// * `record` - The record holding the payload.
+/// * `payload` - The decompressed payload of the record.
+///
+/// # Returns
+///
+/// * The decoded `Message`, timestamped in milliseconds.
+///
+/// # Errors
+///
+/// * If the format, its [`SCHEMA_VERSION_HEADER`] or its [`TIMESTAMP_UNIT_HEADER`] is unknown.
+/// * If the payload doesn't hold a valid message.
+fn decode(record: &BorrowedMessage<'_>, payload: &[u8]) -> Result<Message> {
+    if let Some(version) = header(record, SCHEMA_VERSION_HEADER) {
+        let is_current = str::from_utf8(version)
+            .ok()
+            .and_then(|x| x.parse::<u8>().ok())
+            .is_some_and(|x| x == CURRENT_VERSION);
+        if !is_current {
+            bail!(
+                "Unsupported schema version: {:?}!",
+                String::from_utf8_lossy(version)
+            );
+        }
+    }
+
+    let format = match header(record, CONTENT_TYPE_HEADER) {
+        Some(content_type) => Format::from_content_type(content_type).with_context(|| {
+            format!(
+                "Unknown content type: {:?}!",
+                String::from_utf8_lossy(content_type)
+            )
+        })?,
+        None => Format::Json,
+    };
+
+    let unit = match header(record, TIMESTAMP_UNIT_HEADER) {
+        Some(symbol) => TimestampUnit::from_symbol(symbol).with_context(|| {
+            format!(
+                "Unknown timestamp unit: {:?}!",
+                String::from_utf8_lossy(symbol)
+            )
+        })?,
+        None => TimestampUnit::Millis,
+    };
+
+    Ok(unit.revert(format.decode(payload)?))
+}
+
+/// Decompress the payload of a record, as named by its [`CONTENT_ENCODING_HEADER`].
+///
+/// # Arguments
+///
+/// * `record` - The record holding the payload.
+/// * `payload` - The payload of the record.
+///
+/// # Returns
+///
+/// * The decompressed payload, the payload itself if it isn't compressed.
+///
+/// # Errors
+///
+/// * If the compression is unknown.
+/// * If the payload isn't validly compressed.
+fn decompress(record: &BorrowedMessage<'_>, payload: &[u8]) -> Result<Vec<u8>> {
+    let Some(encoding) = header(record, CONTENT_ENCODING_HEADER) else {
+        return Ok(payload.to_vec());
+    };
+
+    Compression::from_content_encoding(encoding)
+        .with_context(|| {
+            format!(
+                "Unknown content encoding: {:?}!",
+                String::from_utf8_lossy(encoding)
+            )
+        })?
+        .decompress(payload)
+}
+
+/// Create a Kafka consumer joining a consumer group, reading new partitions from the start.
+///
+/// # Arguments
+///
+/// * `bootstrap_server` - A comma-separated list of brokers to connect to.
+/// * `group_id` - The consumer group to join.
+/// * `commit_mode` - How the offsets of the consumed messages are committed.
+///
+/// # Returns
+///
+/// * The created `StreamConsumer`.
+///
+/// # Errors
+///
+/// * If the Kafka client couldn't be created.
+fn create_consumer(
+    bootstrap_server: &str,
+    group_id: &str,
+    commit_mode: CommitMode,
+) -> Result<StreamConsumer> {
+    let consumer = ClientConfig::new()
+        .set("bootstrap.servers", bootstrap_server)
+        .set("group.id", group_id)
+        .set("auto.offset.reset", "earliest")
+        .set("enable.auto.commit", commit_mode.enable_auto_commit())
+        .create()?;
+
+    Ok(consumer)
+}
@@ -0,0 +1,190 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
+
+/// How long to wait for the result of a probe before checking the breaker again.
+const PROBE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The states of a `CircuitBreaker`.
+///
+/// # Variants
+///
+/// * `Closed` - Messages are sent as usual.
+/// * `Open` - Sending is paused until the cooldown is over.
+/// * `HalfOpen` - A single probe message was let through, and its result is awaited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// What a `CircuitBreaker` lets a sender do.
+///
+/// # Variants
+///
+/// * `Send` - Messages may be sent as usual.
+/// * `Probe` - A single message may be sent to probe whether the failures are over.
+/// * `Wait` - Nothing may be sent for the given time, after which the breaker is checked again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Send,
+    Probe,
+    Wait(Duration),
+}
+
+/// The mutable state of a `CircuitBreaker`.
+#[derive(Debug)]
+struct Inner {
+    state: BreakerState,
+    failures: u32,
+    opened_at: Instant,
+}
+
+/// A circuit breaker pausing production after consecutive failures, so that an unreachable
+/// cluster doesn't cause a storm of failing sends.
+///
+/// After `threshold` consecutive failures the breaker opens, pausing for the `cooldown`. A single
+/// probe message is then let through, closing the breaker if it succeeds and reopening it if it
+/// fails.
+///
+/// # Fields
+///
+/// * `threshold` - The number of consecutive failures opening the breaker.
+/// * `cooldown` - The time to pause for once the breaker opens.
+/// * `inner` - The state of the breaker.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// Construct a new, closed `CircuitBreaker`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The number of consecutive failures opening the breaker.
+    /// * `cooldown` - The time to pause for once the breaker opens.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `CircuitBreaker`.
+    #[must_use]
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                failures: 0,
+                opened_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Get the current state of the breaker.
+    ///
+    /// # Returns
+    ///
+    /// * The state of the breaker.
+    ///
+    /// # Panics
+    ///
+    /// * If the lock is poisoned.
+    #[must_use]
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().expect("Lock poisoned!").state
+    }
+
+    /// Check what may be sent, letting a probe through once the cooldown is over.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// * Whether messages may be sent, a single probe may be sent, or how long to wait.
+    ///
+    /// # Panics
+    ///
+    /// * If the lock is poisoned.
+    pub fn check(&self, now: Instant) -> Admission {
+        let mut inner = self.inner.lock().expect("Lock poisoned!");
+        match inner.state {
+            BreakerState::Closed => Admission::Send,
+            BreakerState::Open => {
+                let reopens_at = inner.opened_at + self.cooldown;
+                if now < reopens_at {
+                    return Admission::Wait(reopens_at - now);
+                }
+
+                info!("Circuit breaker half-open, probing with a single message.");
+                inner.state = BreakerState::HalfOpen;
+
+                Admission::Probe
+            }
+            BreakerState::HalfOpen => Admission::Wait(PROBE_POLL_INTERVAL),
+        }
+    }
+
+    /// Record a successfully sent message, closing the breaker if it was probing.
+    ///
+    /// # Panics
+    ///
+    /// * If the lock is poisoned.
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().expect("Lock poisoned!");
+        match inner.state {
+            BreakerState::Closed => inner.failures = 0,
+            BreakerState::HalfOpen => {
+                info!("Probe succeeded, circuit breaker closed.");
+                inner.state = BreakerState::Closed;
+                inner.failures = 0;
+            }
+            // Sent before the breaker opened, saying nothing about the cluster now.
+            BreakerState::Open => {}
+        }
+    }
+
+    /// Record a message that failed to be sent, opening the breaker after too many consecutive
+    /// failures, or if it was probing.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    ///
+    /// # Panics
+    ///
+    /// * If the lock is poisoned.
+    pub fn record_failure(&self, now: Instant) {
+        let mut inner = self.inner.lock().expect("Lock poisoned!");
+        match inner.state {
+            BreakerState::Closed => {
+                inner.failures += 1;
+                if inner.failures >= self.threshold {
+                    warn!(
+                        "Circuit breaker opened after {} consecutive failures, pausing for {:?}!",
+                        inner.failures, self.cooldown,
+                    );
+                    inner.state = BreakerState::Open;
+                    inner.opened_at = now;
+                }
+            }
+            BreakerState::HalfOpen => {
+                warn!(
+                    "Probe failed, circuit breaker reopened, pausing for {:?}!",
+                    self.cooldown,
+                );
+                inner.state = BreakerState::Open;
+                inner.opened_at = now;
+            }
+            BreakerState::Open => {}
+        }
+    }
+}
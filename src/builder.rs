@@ -0,0 +1,97 @@
+use crate::{
+    clock::{Clock, SystemClock},
+    error::MessageError,
+    Message, MilliwattHours,
+};
+
+/// A builder for `Message`s, naming each field at the call site.
+///
+/// # Fields
+///
+/// * `customer_id` - The ID of the customer.
+/// * `consumption` - The mWh of the customer's electrical consumption.
+/// * `timestamp` - The time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessageBuilder {
+    customer_id: Option<u32>,
+    consumption: Option<MilliwattHours>,
+    timestamp: Option<u128>,
+}
+
+impl MessageBuilder {
+    /// Construct a new `MessageBuilder` with no fields set.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `MessageBuilder`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            customer_id: None,
+            consumption: None,
+            timestamp: None,
+        }
+    }
+
+    /// Set the customer ID of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_id` - The ID of the customer.
+    #[must_use]
+    pub const fn customer_id(mut self, customer_id: u32) -> Self {
+        self.customer_id = Some(customer_id);
+
+        self
+    }
+
+    /// Set the electrical consumption of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `consumption` - The mWh of the customer's electrical consumption.
+    #[must_use]
+    pub const fn consumption(mut self, consumption: MilliwattHours) -> Self {
+        self.consumption = Some(consumption);
+
+        self
+    }
+
+    /// Set the timestamp of the message, defaulting to the current time if never set.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    #[must_use]
+    pub const fn timestamp(mut self, timestamp: u128) -> Self {
+        self.timestamp = Some(timestamp);
+
+        self
+    }
+
+    /// Build the `Message`, validating its values like [`Message::try_new`].
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If either the `customer_id` or the `consumption` wasn't set.
+    /// * If the values are rejected by [`Message::try_new`].
+    /// * If no timestamp was set and the system time is before the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    pub fn build(self) -> Result<Message, MessageError> {
+        let customer_id = self
+            .customer_id
+            .ok_or(MessageError::MissingField("customer_id"))?;
+        let consumption = self
+            .consumption
+            .ok_or(MessageError::MissingField("consumption"))?;
+        let timestamp = match self.timestamp {
+            Some(v) => v,
+            None => SystemClock.now_millis()?,
+        };
+
+        Message::try_new(customer_id, consumption, timestamp)
+    }
+}
@@ -0,0 +1,44 @@
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::ClockError;
+
+/// A source of the current time, used to timestamp generated messages.
+pub trait Clock: Debug + Send + Sync {
+    /// Get the current time.
+    ///
+    /// # Returns
+    ///
+    /// * The time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    ///
+    /// # Errors
+    ///
+    /// * If the time is before the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    fn now_millis(&self) -> Result<u128, ClockError>;
+}
+
+/// A clock reading the time of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> Result<u128, ClockError> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_millis())
+            .map_err(|_| ClockError::BeforeEpoch)
+    }
+}
+
+/// A clock stuck at a single point in time, making the timestamps of generated messages
+/// deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub u128);
+
+impl Clock for FixedClock {
+    fn now_millis(&self) -> Result<u128, ClockError> {
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,119 @@
+use std::io::{Read, Write};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use flate2::{read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+/// The record header naming the compression of the payload.
+pub const CONTENT_ENCODING_HEADER: &str = "content-encoding";
+
+/// The compressions applied to an encoded payload before it's sent.
+///
+/// Unlike the compression of the Kafka client, the payload stays compressed on the brokers and
+/// has to be decompressed by the consumers, see [`CONTENT_ENCODING_HEADER`].
+///
+/// # Variants
+///
+/// * `None` - The payload is sent as is.
+/// * `Gzip` - The payload is compressed with [gzip](https://www.gnu.org/software/gzip/).
+/// * `Lz4` - The payload is compressed with [LZ4](https://lz4.org), prefixed with its size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Lz4,
+}
+
+impl Compression {
+    /// Compress a payload.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload to compress.
+    ///
+    /// # Returns
+    ///
+    /// * The compressed payload.
+    ///
+    /// # Errors
+    ///
+    /// * If the payload couldn't be compressed.
+    pub fn compress(self, payload: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(payload),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&payload)?;
+
+                Ok(encoder.finish()?)
+            }
+            Self::Lz4 => Ok(lz4_flex::compress_prepend_size(&payload)),
+        }
+    }
+
+    /// Decompress a payload.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The compressed payload.
+    ///
+    /// # Returns
+    ///
+    /// * The decompressed payload.
+    ///
+    /// # Errors
+    ///
+    /// * If the payload isn't validly compressed.
+    pub fn decompress(self, payload: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(payload.to_vec()),
+            Self::Gzip => {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(payload)
+                    .read_to_end(&mut decompressed)
+                    .context("Invalid gzip payload!")?;
+
+                Ok(decompressed)
+            }
+            Self::Lz4 => {
+                lz4_flex::decompress_size_prepended(payload).context("Invalid LZ4 payload!")
+            }
+        }
+    }
+
+    /// Get the value of the [`CONTENT_ENCODING_HEADER`] for this compression.
+    ///
+    /// # Returns
+    ///
+    /// * The name of the compression, `None` if the payload isn't compressed.
+    #[must_use]
+    pub const fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Lz4 => Some("lz4"),
+        }
+    }
+
+    /// Get the compression named by a [`CONTENT_ENCODING_HEADER`].
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - The value of the header.
+    ///
+    /// # Returns
+    ///
+    /// * The named compression, `None` if it's unknown.
+    #[must_use]
+    pub fn from_content_encoding(encoding: &[u8]) -> Option<Self> {
+        match encoding {
+            b"gzip" => Some(Self::Gzip),
+            b"lz4" => Some(Self::Lz4),
+            b"identity" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,1981 @@
+use std::{
+    collections::HashMap,
+    env::{self, VarError},
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[cfg(feature = "metrics")]
+use std::net::SocketAddr;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    compression::Compression, format::Format, manifest::Manifest, model::ConsumptionModel,
+    partition::topic_for, ramp::RateRamp, sanitize::Sanitize, source::CustomerMode,
+    timestamp::TimestampUnit, CUSTOMER_ID_RANGE, DEFAULT_MAX_CONSUMPTION,
+};
+
+/// The brokers of the school cluster, used when none are given.
+pub const DEFAULT_BROKERS: [&str; 11] = [
+    "172.16.250.32:9092",
+    "172.16.250.33:9092",
+    "172.16.250.34:9092",
+    "172.16.250.35:9092",
+    "172.16.250.36:9092",
+    "172.16.250.37:9092",
+    "172.16.250.38:9092",
+    "172.16.250.39:9092",
+    "172.16.250.40:9092",
+    "172.16.250.41:9092",
+    "172.16.250.42:9092",
+];
+
+/// The topic to produce to, used when none is given.
+pub const DEFAULT_TOPIC: &str = "household_consumption2";
+
+/// The number of messages allowed to await delivery at once, used when none is given.
+pub const DEFAULT_MAX_INFLIGHT: u32 = 100_000;
+
+/// The most times a message failing with a retryable error is retried, used when none is given.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// The milliseconds to wait before the first retry of a message, used when none is given.
+pub const DEFAULT_RETRY_BASE_DELAY: u64 = 100;
+
+/// The seconds to wait for the queued messages to be delivered when shutting down, used when
+/// none is given.
+pub const DEFAULT_FLUSH_TIMEOUT: u64 = 30;
+
+/// The seconds to pause producing for once the circuit breaker opens, used when none is given.
+pub const DEFAULT_BREAKER_COOLDOWN: u64 = 10;
+
+/// The largest payload in bytes sent to the brokers, used when none is given. Matches the
+/// default `message.max.bytes` of the Kafka client.
+pub const DEFAULT_MAX_BYTES: usize = 1_000_000;
+
+/// The names of the fields of a message in JSON, which extra fields mustn't reuse.
+const MESSAGE_FIELDS: [&str; 5] = [
+    "customer_id",
+    "customerId",
+    "consumption",
+    "consumptionMwh",
+    "timestamp",
+];
+
+/// The consumer group to join, used when none is given.
+pub const DEFAULT_GROUP_ID: &str = "h4-bigdata-consumer";
+
+/// The environment variable holding a comma-separated list of brokers.
+pub const BROKERS_VAR: &str = "KAFKA_BROKERS";
+
+/// The environment variable holding the topic to produce to.
+pub const TOPIC_VAR: &str = "KAFKA_TOPIC";
+
+/// The environment variable holding the SASL password, kept out of the shell history.
+pub const SASL_PASSWORD_VAR: &str = "KAFKA_SASL_PASSWORD";
+
+/// The protocols used to communicate with the brokers.
+///
+/// # Variants
+///
+/// * `Plaintext` - Unauthenticated and unencrypted.
+/// * `Ssl` - Encrypted with TLS.
+/// * `SaslPlaintext` - Authenticated with SASL, but unencrypted.
+/// * `SaslSsl` - Authenticated with SASL and encrypted with TLS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecurityProtocol {
+    Plaintext,
+    Ssl,
+    SaslPlaintext,
+    SaslSsl,
+}
+
+impl SecurityProtocol {
+    /// Get the value of the `security.protocol` client property.
+    ///
+    /// # Returns
+    ///
+    /// * The name of the protocol, as understood by the Kafka client.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Plaintext => "plaintext",
+            Self::Ssl => "ssl",
+            Self::SaslPlaintext => "sasl_plaintext",
+            Self::SaslSsl => "sasl_ssl",
+        }
+    }
+
+    /// Check whether the protocol authenticates with SASL.
+    ///
+    /// # Returns
+    ///
+    /// * Whether SASL credentials are required.
+    #[must_use]
+    pub const fn is_sasl(self) -> bool {
+        matches!(self, Self::SaslPlaintext | Self::SaslSsl)
+    }
+}
+
+/// The delivery guarantees of the producer, trading throughput for durability.
+///
+/// # Variants
+///
+/// * `AtMostOnce` - Messages aren't acknowledged or retried, so they're lost if a broker fails,
+///   but never duplicated. The fastest option.
+/// * `AtLeastOnce` - Messages are acknowledged by every in-sync replica and retried, so they
+///   survive a failing broker, but may be duplicated by a retry.
+/// * `ExactlyOnce` - Like `AtLeastOnce`, but the idempotent producer lets the brokers discard
+///   duplicated retries, at the cost of throughput.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeliveryGuarantee {
+    AtMostOnce,
+    AtLeastOnce,
+    ExactlyOnce,
+}
+
+impl DeliveryGuarantee {
+    /// Get the client properties providing the guarantee.
+    ///
+    /// # Returns
+    ///
+    /// * The properties, as keys and values.
+    #[must_use]
+    pub const fn properties(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::AtMostOnce => &[
+                ("enable.idempotence", "false"),
+                ("acks", "0"),
+                ("message.send.max.retries", "0"),
+            ],
+            // Without idempotence, a single request in flight keeps retries from reordering.
+            Self::AtLeastOnce => &[
+                ("enable.idempotence", "false"),
+                ("acks", "all"),
+                ("message.send.max.retries", "10"),
+                ("max.in.flight.requests.per.connection", "1"),
+            ],
+            Self::ExactlyOnce => &[
+                ("enable.idempotence", "true"),
+                ("acks", "all"),
+                ("message.send.max.retries", "10"),
+                ("max.in.flight.requests.per.connection", "5"),
+            ],
+        }
+    }
+}
+
+/// The acknowledgements the producer waits for before a message counts as delivered.
+///
+/// # Variants
+///
+/// * `None` - Messages aren't acknowledged at all, so they're lost if the leader fails before
+///   writing them. The lowest latency.
+/// * `Leader` - Messages are acknowledged once the leader wrote them, so they're lost if it fails
+///   before its followers copied them.
+/// * `All` - Messages are acknowledged once every in-sync replica wrote them, so they survive a
+///   failing broker. The highest latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum Acks {
+    #[value(name = "0")]
+    #[serde(rename = "0")]
+    None,
+    #[value(name = "1")]
+    #[serde(rename = "1")]
+    Leader,
+    #[value(name = "all")]
+    #[serde(rename = "all")]
+    All,
+}
+
+impl Acks {
+    /// Get the value of the `acks` client property.
+    ///
+    /// # Returns
+    ///
+    /// * The value of the property.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "0",
+            Self::Leader => "1",
+            Self::All => "all",
+        }
+    }
+}
+
+/// How messages are keyed, deciding the partitions they're spread across.
+///
+/// # Variants
+///
+/// * `Keyed` - Messages are keyed by customer, so the messages of a customer stay in order on a
+///   single partition.
+/// * `None` - Messages aren't keyed, and every message goes to a random partition, spreading
+///   them evenly but without any order across partitions.
+/// * `Sticky` - Messages aren't keyed, and go to a single partition until its batch is sent, see
+///   [KIP-480](https://cwiki.apache.org/confluence/display/KAFKA/KIP-480%3A+Sticky+Partitioner),
+///   making for fewer and larger requests but without any order across partitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Keying {
+    #[default]
+    Keyed,
+    None,
+    Sticky,
+}
+
+impl Keying {
+    /// Get the client properties partitioning the messages.
+    ///
+    /// # Returns
+    ///
+    /// * The properties, as keys and values.
+    #[must_use]
+    pub const fn properties(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Keyed => &[],
+            // Sticking to a partition for no time at all picks a new one for every message.
+            Self::None => &[("sticky.partitioning.linger.ms", "0")],
+            Self::Sticky => &[("sticky.partitioning.linger.ms", "10")],
+        }
+    }
+
+    /// Check whether messages are produced with a key.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the messages are keyed by customer.
+    #[must_use]
+    pub const fn is_keyed(self) -> bool {
+        matches!(self, Self::Keyed)
+    }
+}
+
+/// The formats log lines are written in.
+///
+/// # Variants
+///
+/// * `Text` - Human-readable lines, with the fields interpolated into the message.
+/// * `Json` - One JSON object per line, keeping the fields of an event as structured data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// The most verbose level of the log lines that are written.
+///
+/// # Variants
+///
+/// * `Trace` - Everything, including the internals of the dependencies.
+/// * `Debug` - Every produced message.
+/// * `Info` - Progress and throughput reports.
+/// * `Warn` - Dropped messages and other recoverable problems.
+/// * `Error` - Failures to produce messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Get the directive filtering the log lines by this level.
+    ///
+    /// # Returns
+    ///
+    /// * The name of the level, as understood by `tracing_subscriber::EnvFilter`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// The mechanisms used to authenticate with SASL.
+///
+/// # Variants
+///
+/// * `Plain` - The credentials are sent as is, only safe over TLS.
+/// * `ScramSha256` - A SCRAM challenge hashed with SHA-256.
+/// * `ScramSha512` - A SCRAM challenge hashed with SHA-512.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SaslMechanism {
+    Plain,
+    #[value(name = "scram-sha-256")]
+    #[serde(rename = "scram-sha-256")]
+    ScramSha256,
+    #[value(name = "scram-sha-512")]
+    #[serde(rename = "scram-sha-512")]
+    ScramSha512,
+}
+
+impl SaslMechanism {
+    /// Get the value of the `sasl.mechanism` client property.
+    ///
+    /// # Returns
+    ///
+    /// * The name of the mechanism, as understood by the Kafka client.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Plain => "PLAIN",
+            Self::ScramSha256 => "SCRAM-SHA-256",
+            Self::ScramSha512 => "SCRAM-SHA-512",
+        }
+    }
+}
+
+/// The ways the consumer commits the offsets of the messages it has consumed.
+///
+/// # Variants
+///
+/// * `Auto` - The Kafka client commits periodically in the background, which may skip messages
+///   that were consumed but not yet processed when the consumer crashed.
+/// * `Manual` - Each message is committed once it has been processed, so that every message is
+///   processed at least once, but may be processed again after a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CommitMode {
+    #[default]
+    Auto,
+    Manual,
+}
+
+impl CommitMode {
+    /// Get the value of the `enable.auto.commit` client property.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the Kafka client should commit the offsets by itself.
+    #[must_use]
+    pub const fn enable_auto_commit(self) -> &'static str {
+        match self {
+            Self::Auto => "true",
+            Self::Manual => "false",
+        }
+    }
+}
+
+/// The default number of seconds to wait for the brokers to respond to a health check.
+pub const DEFAULT_HEALTHCHECK_TIMEOUT: u64 = 10;
+
+/// The commands run instead of producing messages.
+///
+/// # Variants
+///
+/// * `Healthcheck` - Check that the brokers respond within `timeout` seconds, and exit.
+/// * `Tombstone` - Send a tombstone for each of the `customer_ids`, deleting them from a compacted
+///   topic, and exit.
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Check that the brokers respond, and whether the topic exists.
+    Healthcheck {
+        /// The number of seconds to wait for the brokers to respond.
+        #[arg(long, default_value_t = DEFAULT_HEALTHCHECK_TIMEOUT)]
+        timeout: u64,
+    },
+    /// Send tombstones deleting customers from a compacted topic.
+    Tombstone {
+        /// The IDs of the customers to delete.
+        #[arg(required = true)]
+        customer_ids: Vec<u32>,
+    },
+}
+
+/// The command-line arguments of the producer.
+///
+/// Every value is optional, so that unset flags fall back to the environment.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// A command to run instead of producing messages.
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// A TOML file to read the configuration from, overridden by the environment and the flags.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// A run manifest to reproduce, read like `--config`, see `--manifest`.
+    #[arg(long, value_name = "PATH", conflicts_with = "config")]
+    from_manifest: Option<PathBuf>,
+
+    /// Write a manifest of the run to this JSON file at startup, recording the seed and the
+    /// configuration needed to reproduce it with `--from-manifest`.
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+
+    /// Comma-separated list of Kafka brokers [env: KAFKA_BROKERS].
+    #[arg(long, value_delimiter = ',')]
+    brokers: Option<Vec<String>>,
+
+    /// The topic to produce messages to [env: KAFKA_TOPIC].
+    #[arg(long)]
+    topic: Option<String>,
+
+    /// The environment to namespace the topic by, e.g. `prod`, producing to `prod.<topic>`.
+    #[arg(long, value_name = "ENV")]
+    topic_prefix: Option<String>,
+
+    /// The number of messages to produce per second, unbounded if unset.
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// Raise the number of messages produced per second linearly from one rate to another over a
+    /// number of seconds, then hold it, formatted as `<start>:<end>:<seconds>`.
+    #[arg(long, value_parser = parse_ramp, conflicts_with = "rate")]
+    ramp: Option<RateRamp>,
+
+    /// The seed for generating a reproducible stream of messages.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// The wire format to encode messages in.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// The compression to apply to the encoded messages, separate from the Kafka client's.
+    #[arg(long, value_enum)]
+    compress: Option<Compression>,
+
+    /// The largest encoded payload in bytes to send, rejecting larger messages before they reach
+    /// the brokers.
+    #[arg(long, value_name = "BYTES")]
+    max_bytes: Option<usize>,
+
+    /// The number of messages to enqueue together before awaiting their delivery.
+    #[arg(long)]
+    batch_size: Option<usize>,
+
+    /// Print the JSON Schema of the messages and exit.
+    #[arg(long)]
+    print_schema: bool,
+
+    /// Print how the messages would be spread across this many partitions, and exit.
+    #[arg(long)]
+    key_distribution: Option<i32>,
+
+    /// Print the messages to stdout instead of producing them to Kafka.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// A file to append every message to, one per line, also when doing a dry run. Messages are
+    /// written before they're sent, so the file records every attempted message, including ones
+    /// that fail to be delivered.
+    #[arg(long)]
+    output_file: Option<PathBuf>,
+
+    /// A JSON Lines file to append messages that failed to be produced to, with the reason.
+    #[arg(long)]
+    dead_letter: Option<PathBuf>,
+
+    /// The number of most recently produced messages to keep, dumped as JSON on SIGUSR1 or on
+    /// `/debug/recent` of the metrics server. `0` disables it.
+    #[arg(long, value_name = "N")]
+    recent_buffer: Option<usize>,
+
+    /// The format to write log lines in, `json` for log pipelines parsing structured fields.
+    #[arg(long, value_enum)]
+    log_format: Option<LogFormat>,
+
+    /// The most verbose level of log lines to write, read from `RUST_LOG` if unset.
+    #[arg(long, value_enum)]
+    log_level: Option<LogLevel>,
+
+    /// Only write warnings and errors, short for `--log-level warn`.
+    #[arg(long, short, conflicts_with = "log_level")]
+    quiet: bool,
+
+    /// The number of partitions of the topic, to pin each customer to a fixed partition, counted
+    /// from the topic if unset.
+    #[arg(long)]
+    partitions: Option<i32>,
+
+    /// How messages are keyed, by customer to keep their order, or not at all to spread them
+    /// evenly across partitions.
+    #[arg(long, value_enum)]
+    keying: Option<Keying>,
+
+    /// The number of topics to shard the customers across, suffixing the topic with
+    /// `_{customer_id % N}`.
+    #[arg(long)]
+    topic_shards: Option<u32>,
+
+    /// Create the topic if it doesn't exist, with `--partitions` partitions, instead of exiting.
+    #[arg(long)]
+    create_topic: bool,
+
+    /// The replication factor of a topic created with `--create-topic`, the broker default if
+    /// unset.
+    #[arg(long)]
+    replication_factor: Option<i32>,
+
+    /// The number of seconds between throughput reports, `0` disables them.
+    #[arg(long)]
+    stats_interval: Option<u64>,
+
+    /// Additionally poll the Kafka client for delivery reports every this many milliseconds,
+    /// on top of its own polling thread.
+    #[arg(long, value_name = "MS")]
+    poll_interval: Option<u64>,
+
+    /// The number of seconds to wait before reporting throughput, ignoring the messages produced
+    /// in the meantime.
+    #[arg(long)]
+    warmup: Option<u64>,
+
+    /// The number of messages to produce before exiting, unbounded if unset.
+    #[arg(long)]
+    count: Option<u64>,
+
+    /// The number of messages allowed to await delivery at once.
+    #[arg(long)]
+    max_inflight: Option<u32>,
+
+    /// The number of workers generating and producing messages concurrently, sharing the rate and
+    /// count between them. Each worker's generator is seeded with `--seed` plus its index.
+    #[arg(long, conflicts_with = "replay")]
+    workers: Option<u32>,
+
+    /// The most times to retry a message failing with a transient error, like a timeout, before
+    /// giving up on it.
+    #[arg(long)]
+    max_retries: Option<u32>,
+
+    /// The number of milliseconds to wait before the first retry of a message, doubled on every
+    /// retry.
+    #[arg(long, value_name = "MS")]
+    retry_base_delay: Option<u64>,
+
+    /// The number of seconds to wait for the queued messages to be delivered when shutting down,
+    /// before giving up on them.
+    #[arg(long, value_name = "SECS")]
+    flush_timeout: Option<u64>,
+
+    /// Pause producing after this many consecutive messages failed to be produced, probing with
+    /// a single message after `--breaker-cooldown`.
+    #[arg(long)]
+    error_threshold: Option<u32>,
+
+    /// The number of seconds to pause producing for once `--error-threshold` is reached.
+    #[arg(long, value_name = "SECS")]
+    breaker_cooldown: Option<u64>,
+
+    /// The inclusive range of customer IDs to generate, formatted as `<min>-<max>`.
+    #[arg(long, value_parser = parse_customer_range)]
+    customer_range: Option<RangeInclusive<u32>>,
+
+    /// The largest consumption in mWh to generate.
+    #[arg(long)]
+    max_consumption: Option<f32>,
+
+    /// A CSV or JSON file of consumption profiles, sampling the consumption of the customers in
+    /// it from their own profile.
+    #[arg(long)]
+    profiles: Option<PathBuf>,
+
+    /// Offset each generated timestamp by a random number of milliseconds, up to this many in
+    /// either direction.
+    #[arg(long, value_name = "MS")]
+    timestamp_jitter: Option<u64>,
+
+    /// The unit the timestamps are sent in, named by a `timestamp-unit` record header.
+    #[arg(long, value_enum)]
+    timestamp_unit: Option<TimestampUnit>,
+
+    /// Pick customers from a Zipf distribution with this exponent, so that the lowest IDs are
+    /// picked far more often. 0 picks uniformly.
+    #[arg(long, value_name = "ALPHA", conflicts_with_all = ["meters", "replay"])]
+    skew: Option<f64>,
+
+    /// How to pick the customers from `--customer-range`, `sequential` or `hashed` to cycle
+    /// through every customer in a repeatable order.
+    #[arg(long, value_enum, conflicts_with_all = ["meters", "replay", "skew"])]
+    customer_mode: Option<CustomerMode>,
+
+    /// The number of simulated meters to read in turn, with correlated readings following a daily
+    /// profile, instead of independent random messages.
+    #[arg(long, conflicts_with = "replay")]
+    meters: Option<usize>,
+
+    /// A JSON Lines file of recorded messages to replay, instead of generating random ones.
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// A CSV file of historical messages to ingest, with the columns `customer_id`, `consumption`
+    /// and `timestamp`, instead of generating random ones.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["meters", "replay", "skew"])]
+    ingest_csv: Option<PathBuf>,
+
+    /// Only check that every record of the `--replay` or `--ingest-csv` file parses, exiting with
+    /// an error if any doesn't, without producing anything.
+    #[arg(long)]
+    validate_only: bool,
+
+    /// Preserve the time between the replayed messages, based on their timestamps.
+    #[arg(long)]
+    replay_realtime: bool,
+
+    /// Replay this many times faster than the recording, as fast as possible if 0. Implies
+    /// `--replay-realtime`.
+    #[arg(long, value_name = "FACTOR")]
+    time_scale: Option<f64>,
+
+    /// Drop messages with timestamps more than this many milliseconds in the future.
+    #[arg(long)]
+    reject_future_skew: Option<u128>,
+
+    /// Drop messages with customer IDs outside of the 1000-9999 allowed by the schema.
+    #[arg(long)]
+    strict_customer_id: bool,
+
+    /// Replace NaN or infinite consumptions with 0 mWh, or reject their messages, before they're
+    /// sent, rather than encoding them as `null` in JSON.
+    #[arg(long, value_enum)]
+    sanitize: Option<Sanitize>,
+
+    /// The delivery guarantee of the producer, the Kafka client's defaults if unset.
+    #[arg(long, value_enum)]
+    delivery_guarantee: Option<DeliveryGuarantee>,
+
+    /// The acknowledgements to wait for before a message counts as delivered, overriding the
+    /// delivery guarantee: none with `0`, the leader's with `1`, or every in-sync replica's with
+    /// `all`.
+    #[arg(long, value_enum)]
+    acks: Option<Acks>,
+
+    /// How long to wait for more messages to batch before sending a request, in milliseconds,
+    /// trading latency for throughput.
+    #[arg(long)]
+    linger_ms: Option<u64>,
+
+    /// The protocol used to communicate with the brokers.
+    #[arg(long, value_enum)]
+    security_protocol: Option<SecurityProtocol>,
+
+    /// The mechanism used to authenticate with SASL.
+    #[arg(long, value_enum)]
+    sasl_mechanism: Option<SaslMechanism>,
+
+    /// The username to authenticate with SASL.
+    #[arg(long)]
+    sasl_username: Option<String>,
+
+    /// The password to authenticate with SASL [env: KAFKA_SASL_PASSWORD].
+    #[arg(long)]
+    sasl_password: Option<String>,
+
+    /// An extra Kafka client property, formatted as `<key>=<value>`, may be repeated.
+    #[arg(long = "kafka-config", value_parser = parse_key_value)]
+    kafka_config: Vec<(String, String)>,
+
+    /// An extra field attached to every JSON message, formatted as `<name>=<value>`, may be
+    /// repeated.
+    #[arg(long = "extra-field", value_parser = parse_key_value)]
+    extra_fields: Vec<(String, String)>,
+
+    /// The address to serve Prometheus metrics on, disabled if unset.
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// The URL of a Confluent Schema Registry to register the Avro schema with, framing every
+    /// payload with its ID.
+    #[cfg(feature = "schema-registry")]
+    #[arg(long)]
+    schema_registry_url: Option<String>,
+}
+
+/// A layer of the configuration of the producer, as read from a TOML file, from the JSON of a
+/// run manifest, or from the command-line flags.
+///
+/// Every value is optional, so that unset keys fall back to the layers below. See [`Config`] for
+/// the meaning of each key. The values that are only given as flags are skipped in files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    brokers: Option<Vec<String>>,
+    topic: Option<String>,
+    topic_prefix: Option<String>,
+    rate: Option<f64>,
+    #[serde(
+        deserialize_with = "deserialize_ramp",
+        serialize_with = "serialize_ramp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    ramp: Option<RateRamp>,
+    seed: Option<u64>,
+    format: Option<Format>,
+    compress: Option<Compression>,
+    max_bytes: Option<usize>,
+    batch_size: Option<usize>,
+    #[serde(skip)]
+    print_schema: Option<bool>,
+    #[serde(skip)]
+    key_distribution: Option<i32>,
+    dry_run: Option<bool>,
+    output_file: Option<PathBuf>,
+    dead_letter: Option<PathBuf>,
+    recent_buffer: Option<usize>,
+    log_format: Option<LogFormat>,
+    log_level: Option<LogLevel>,
+    partitions: Option<i32>,
+    keying: Option<Keying>,
+    topic_shards: Option<u32>,
+    create_topic: Option<bool>,
+    replication_factor: Option<i32>,
+    stats_interval: Option<u64>,
+    poll_interval: Option<u64>,
+    warmup: Option<u64>,
+    count: Option<u64>,
+    max_inflight: Option<u32>,
+    workers: Option<u32>,
+    max_retries: Option<u32>,
+    retry_base_delay: Option<u64>,
+    flush_timeout: Option<u64>,
+    error_threshold: Option<u32>,
+    breaker_cooldown: Option<u64>,
+    #[serde(
+        deserialize_with = "deserialize_customer_range",
+        serialize_with = "serialize_customer_range",
+        skip_serializing_if = "Option::is_none"
+    )]
+    customer_range: Option<RangeInclusive<u32>>,
+    max_consumption: Option<f32>,
+    model: Option<ConsumptionModel>,
+    profiles: Option<PathBuf>,
+    timestamp_jitter: Option<u64>,
+    timestamp_unit: Option<TimestampUnit>,
+    skew: Option<f64>,
+    customer_mode: Option<CustomerMode>,
+    meters: Option<usize>,
+    replay: Option<PathBuf>,
+    ingest_csv: Option<PathBuf>,
+    validate_only: Option<bool>,
+    replay_realtime: Option<bool>,
+    time_scale: Option<f64>,
+    reject_future_skew: Option<u128>,
+    strict_customer_id: Option<bool>,
+    sanitize: Option<Sanitize>,
+    delivery_guarantee: Option<DeliveryGuarantee>,
+    acks: Option<Acks>,
+    linger_ms: Option<u64>,
+    security_protocol: Option<SecurityProtocol>,
+    sasl_mechanism: Option<SaslMechanism>,
+    sasl_username: Option<String>,
+    #[serde(skip)]
+    sasl_password: Option<String>,
+    kafka_config: HashMap<String, String>,
+    extra_fields: HashMap<String, String>,
+    #[cfg(feature = "metrics")]
+    metrics_addr: Option<SocketAddr>,
+    #[cfg(feature = "schema-registry")]
+    schema_registry_url: Option<String>,
+}
+
+/// A command-line value that can be layered over the configuration like a value of a file.
+trait IntoLayer<T> {
+    /// Convert the command-line value into the value of a layer.
+    ///
+    /// # Returns
+    ///
+    /// * The value of the layer, unset if the flag wasn't given.
+    fn into_layer(self) -> T;
+}
+
+impl<T> IntoLayer<Option<T>> for Option<T> {
+    fn into_layer(self) -> Self {
+        self
+    }
+}
+
+impl IntoLayer<Option<bool>> for bool {
+    fn into_layer(self) -> Option<bool> {
+        self.then_some(true)
+    }
+}
+
+impl IntoLayer<HashMap<String, String>> for Vec<(String, String)> {
+    fn into_layer(self) -> HashMap<String, String> {
+        self.into_iter().collect()
+    }
+}
+
+/// Implement the conversions between [`Args`], [`FileConfig`] and [`Config`] from a single list
+/// of their fields.
+///
+/// * `defaulted` - The fields the `Config` holds a default value of.
+/// * `optional` - The fields the `Config` holds an `Option` of.
+/// * `file_only` - Like `optional`, but without a command-line flag.
+/// * `extended` - The maps of the `Config`, extended by every layer.
+macro_rules! layered_config {
+    (
+        defaulted { $($(#[$defaulted_meta:meta])* $defaulted:ident),* $(,)? }
+        optional { $($(#[$optional_meta:meta])* $optional:ident),* $(,)? }
+        file_only { $($file_only:ident),* $(,)? }
+        extended { $($extended:ident),* $(,)? }
+    ) => {
+        impl From<Args> for FileConfig {
+            fn from(args: Args) -> Self {
+                Self {
+                    $($(#[$defaulted_meta])* $defaulted: args.$defaulted.into_layer(),)*
+                    $($(#[$optional_meta])* $optional: args.$optional.into_layer(),)*
+                    $($file_only: None,)*
+                    $($extended: args.$extended.into_layer(),)*
+                }
+            }
+        }
+
+        impl Config {
+            /// Override the configuration with the values set in a layer.
+            ///
+            /// # Arguments
+            ///
+            /// * `layer` - The layer to apply.
+            fn apply(&mut self, layer: FileConfig) {
+                $(
+                    $(#[$defaulted_meta])*
+                    if let Some(value) = layer.$defaulted {
+                        self.$defaulted = value;
+                    }
+                )*
+
+                $(
+                    $(#[$optional_meta])*
+                    if let Some(value) = layer.$optional {
+                        self.$optional = Some(value);
+                    }
+                )*
+
+                $(
+                    if let Some(value) = layer.$file_only {
+                        self.$file_only = Some(value);
+                    }
+                )*
+
+                $(self.$extended.extend(layer.$extended);)*
+            }
+
+            /// Convert the configuration into a layer setting every value.
+            ///
+            /// # Returns
+            ///
+            /// * The `FileConfig`.
+            fn to_file_config(&self) -> FileConfig {
+                FileConfig {
+                    $($(#[$defaulted_meta])* $defaulted: Some(Clone::clone(&self.$defaulted)),)*
+                    $($(#[$optional_meta])* $optional: Clone::clone(&self.$optional),)*
+                    $($file_only: Clone::clone(&self.$file_only),)*
+                    $($extended: self.$extended.clone(),)*
+                }
+            }
+        }
+    };
+}
+
+layered_config! {
+    defaulted {
+        brokers,
+        topic,
+        format,
+        compress,
+        max_bytes,
+        batch_size,
+        print_schema,
+        dry_run,
+        recent_buffer,
+        log_format,
+        keying,
+        topic_shards,
+        create_topic,
+        stats_interval,
+        warmup,
+        max_inflight,
+        workers,
+        max_retries,
+        retry_base_delay,
+        flush_timeout,
+        breaker_cooldown,
+        customer_range,
+        max_consumption,
+        timestamp_jitter,
+        timestamp_unit,
+        skew,
+        customer_mode,
+        validate_only,
+        replay_realtime,
+        strict_customer_id,
+    }
+    optional {
+        topic_prefix,
+        rate,
+        ramp,
+        seed,
+        key_distribution,
+        output_file,
+        dead_letter,
+        log_level,
+        partitions,
+        replication_factor,
+        poll_interval,
+        count,
+        error_threshold,
+        profiles,
+        meters,
+        replay,
+        ingest_csv,
+        time_scale,
+        reject_future_skew,
+        sanitize,
+        delivery_guarantee,
+        acks,
+        linger_ms,
+        security_protocol,
+        sasl_mechanism,
+        sasl_username,
+        sasl_password,
+        #[cfg(feature = "metrics")]
+        metrics_addr,
+        #[cfg(feature = "schema-registry")]
+        schema_registry_url,
+    }
+    file_only {
+        model,
+    }
+    extended {
+        kafka_config,
+        extra_fields,
+    }
+}
+
+/// The configuration of the producer.
+///
+/// # Fields
+///
+/// * `command` - A command to run instead of producing messages.
+/// * `manifest` - The file to write a manifest of the run to, if any.
+/// * `brokers` - The Kafka brokers to connect to.
+/// * `topic` - The topic to produce messages to, before it's prefixed.
+/// * `topic_prefix` - The environment to namespace the topic by, see [`Config::full_topic`].
+/// * `rate` - The number of messages to produce per second, as fast as possible if `None`.
+/// * `ramp` - The ramp of the number of messages to produce per second, taking precedence over
+///   the `rate`.
+/// * `seed` - The seed of the message generator, random if `None`.
+/// * `format` - The wire format to encode messages in.
+/// * `compress` - The compression to apply to the encoded messages.
+/// * `max_bytes` - The largest encoded payload in bytes to send.
+/// * `batch_size` - The number of messages to enqueue together before awaiting their delivery.
+/// * `print_schema` - Whether to print the JSON Schema of the messages instead of producing any.
+/// * `key_distribution` - The number of partitions to print the spread of the messages across,
+///   instead of producing any.
+/// * `dry_run` - Whether to print the messages to stdout instead of producing them to Kafka.
+/// * `output_file` - A file to append every attempted message to, before it's sent, disabled if
+///   `None`.
+/// * `dead_letter` - A file to append messages that failed to be produced to, disabled if `None`.
+/// * `recent_buffer` - The number of most recently produced messages to keep for debugging,
+///   disabled if `0`.
+/// * `log_format` - The format to write log lines in.
+/// * `log_level` - The most verbose level of log lines to write, read from `RUST_LOG` if `None`.
+/// * `partitions` - The number of partitions of the topic, counted from the topic if `None`.
+/// * `keying` - How messages are keyed, deciding the partitions they're spread across.
+/// * `topic_shards` - The number of topics to shard the customers across, `1` to only use `topic`.
+/// * `create_topic` - Whether to create missing topics, rather than failing to start.
+/// * `replication_factor` - The replication factor of created topics, the broker default if
+///   `None`.
+/// * `stats_interval` - The number of seconds between throughput reports, disabled if `0`.
+/// * `poll_interval` - The number of milliseconds between extra polls of the Kafka client, left
+///   to its polling thread if `None`.
+/// * `warmup` - The number of seconds before throughput is reported, ignoring earlier messages.
+/// * `count` - The number of messages to produce before exiting, unbounded if `None`.
+/// * `max_inflight` - The number of messages allowed to await delivery at once.
+/// * `workers` - The number of workers producing messages concurrently, each seeded with `seed`
+///   plus its index.
+/// * `max_retries` - The most times a message failing with a retryable error is retried.
+/// * `retry_base_delay` - The number of milliseconds to wait before the first retry of a message,
+///   doubled on every retry.
+/// * `flush_timeout` - The number of seconds to wait for the queued messages to be delivered when
+///   shutting down.
+/// * `error_threshold` - The number of consecutive failures pausing production, never paused if
+///   `None`.
+/// * `breaker_cooldown` - The number of seconds to pause production for.
+/// * `customer_range` - The inclusive range of customer IDs to generate.
+/// * `max_consumption` - The largest consumption in mWh to generate.
+/// * `model` - The model to sample the consumption from, uniform up to `max_consumption` if
+///   `None`.
+/// * `profiles` - A file of consumption profiles of known customers, see
+///   [`ProfileRegistry::load`](crate::profile::ProfileRegistry::load).
+/// * `timestamp_jitter` - The most milliseconds a generated timestamp is randomly offset by, in
+///   either direction.
+/// * `timestamp_unit` - The unit the timestamps are sent in.
+/// * `skew` - The exponent of the Zipf distribution customers are picked from, uniformly if 0.
+/// * `customer_mode` - How the customers are picked from the `customer_range`.
+/// * `meters` - The number of simulated meters to read, independent random messages if `None`.
+/// * `replay` - A JSON Lines file of recorded messages to replay, random messages if `None`.
+/// * `ingest_csv` - A CSV file of historical messages to ingest, random messages if `None`.
+/// * `validate_only` - Whether to only check that the records of the `replay` or `ingest_csv`
+///   file parse, instead of producing them.
+/// * `replay_realtime` - Whether to preserve the time between the replayed messages.
+/// * `time_scale` - How many times faster than recorded the replayed messages are paced, as fast
+///   as possible if 0, in real time if `None`.
+/// * `reject_future_skew` - The number of milliseconds a timestamp may be in the future before
+///   its message is dropped, never dropped if `None`.
+/// * `strict_customer_id` - Whether to drop messages with customer IDs not allowed by the schema.
+/// * `sanitize` - How messages with non-finite consumptions are handled, sent as is if `None`.
+/// * `delivery_guarantee` - The delivery guarantee of the producer, the Kafka client's defaults
+///   if `None`.
+/// * `acks` - The acknowledgements to wait for, overriding the `delivery_guarantee` if set.
+/// * `linger_ms` - How long to wait for more messages to batch before sending a request, in
+///   milliseconds, `0` if `None`.
+/// * `security_protocol` - The protocol used to communicate with the brokers, plaintext if `None`.
+/// * `sasl_mechanism` - The mechanism used to authenticate with SASL.
+/// * `sasl_username` - The username to authenticate with SASL.
+/// * `sasl_password` - The password to authenticate with SASL.
+/// * `kafka_config` - Extra Kafka client properties, applied on top of the defaults.
+/// * `extra_fields` - Extra fields attached to every message, sent as is if empty.
+/// * `metrics_addr` - The address to serve Prometheus metrics on, disabled if `None`.
+/// * `schema_registry_url` - The URL of the schema registry, payloads are left unframed if `None`.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub command: Option<Command>,
+    pub manifest: Option<PathBuf>,
+    pub brokers: Vec<String>,
+    pub topic: String,
+    pub topic_prefix: Option<String>,
+    pub rate: Option<f64>,
+    pub ramp: Option<RateRamp>,
+    pub seed: Option<u64>,
+    pub format: Format,
+    pub compress: Compression,
+    pub max_bytes: usize,
+    pub batch_size: usize,
+    pub print_schema: bool,
+    pub key_distribution: Option<i32>,
+    pub dry_run: bool,
+    pub output_file: Option<PathBuf>,
+    pub dead_letter: Option<PathBuf>,
+    pub recent_buffer: usize,
+    pub log_format: LogFormat,
+    pub log_level: Option<LogLevel>,
+    pub partitions: Option<i32>,
+    pub keying: Keying,
+    pub topic_shards: u32,
+    pub create_topic: bool,
+    pub replication_factor: Option<i32>,
+    pub stats_interval: u64,
+    pub poll_interval: Option<u64>,
+    pub warmup: u64,
+    pub count: Option<u64>,
+    pub max_inflight: u32,
+    pub workers: u32,
+    pub max_retries: u32,
+    pub retry_base_delay: u64,
+    pub flush_timeout: u64,
+    pub error_threshold: Option<u32>,
+    pub breaker_cooldown: u64,
+    pub customer_range: RangeInclusive<u32>,
+    pub max_consumption: f32,
+    pub model: Option<ConsumptionModel>,
+    pub profiles: Option<PathBuf>,
+    pub timestamp_jitter: u64,
+    pub timestamp_unit: TimestampUnit,
+    pub skew: f64,
+    pub customer_mode: CustomerMode,
+    pub meters: Option<usize>,
+    pub replay: Option<PathBuf>,
+    pub ingest_csv: Option<PathBuf>,
+    pub validate_only: bool,
+    pub replay_realtime: bool,
+    pub time_scale: Option<f64>,
+    pub reject_future_skew: Option<u128>,
+    pub strict_customer_id: bool,
+    pub sanitize: Option<Sanitize>,
+    pub delivery_guarantee: Option<DeliveryGuarantee>,
+    pub acks: Option<Acks>,
+    pub linger_ms: Option<u64>,
+    pub security_protocol: Option<SecurityProtocol>,
+    pub sasl_mechanism: Option<SaslMechanism>,
+    pub sasl_username: Option<String>,
+    pub sasl_password: Option<String>,
+    pub kafka_config: HashMap<String, String>,
+    pub extra_fields: HashMap<String, String>,
+    #[cfg(feature = "metrics")]
+    pub metrics_addr: Option<SocketAddr>,
+    #[cfg(feature = "schema-registry")]
+    pub schema_registry_url: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            command: None,
+            manifest: None,
+            brokers: default_brokers(),
+            topic: DEFAULT_TOPIC.to_string(),
+            topic_prefix: None,
+            rate: None,
+            ramp: None,
+            seed: None,
+            format: Format::default(),
+            compress: Compression::default(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            batch_size: 1,
+            print_schema: false,
+            key_distribution: None,
+            dry_run: false,
+            output_file: None,
+            dead_letter: None,
+            recent_buffer: 0,
+            log_format: LogFormat::Text,
+            log_level: None,
+            partitions: None,
+            keying: Keying::default(),
+            topic_shards: 1,
+            create_topic: false,
+            replication_factor: None,
+            stats_interval: 5,
+            poll_interval: None,
+            warmup: 0,
+            count: None,
+            max_inflight: DEFAULT_MAX_INFLIGHT,
+            workers: 1,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
+            flush_timeout: DEFAULT_FLUSH_TIMEOUT,
+            error_threshold: None,
+            breaker_cooldown: DEFAULT_BREAKER_COOLDOWN,
+            customer_range: CUSTOMER_ID_RANGE,
+            max_consumption: DEFAULT_MAX_CONSUMPTION,
+            model: None,
+            profiles: None,
+            timestamp_jitter: 0,
+            timestamp_unit: TimestampUnit::default(),
+            skew: 0.0,
+            customer_mode: CustomerMode::default(),
+            meters: None,
+            replay: None,
+            ingest_csv: None,
+            validate_only: false,
+            replay_realtime: false,
+            time_scale: None,
+            reject_future_skew: None,
+            strict_customer_id: false,
+            sanitize: None,
+            delivery_guarantee: None,
+            acks: None,
+            linger_ms: None,
+            security_protocol: None,
+            sasl_mechanism: None,
+            sasl_username: None,
+            sasl_password: None,
+            kafka_config: HashMap::new(),
+            extra_fields: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            metrics_addr: None,
+            #[cfg(feature = "schema-registry")]
+            schema_registry_url: None,
+        }
+    }
+}
+
+impl Config {
+    /// Resolve the configuration from the command-line arguments, the environment, and the
+    /// configuration file given with `--config` or the manifest given with `--from-manifest`.
+    ///
+    /// Command-line flags take precedence over environment variables, which take precedence over
+    /// the configuration file, which in turn takes precedence over the defaults.
+    ///
+    /// Exits the process with a usage message if the arguments are invalid.
+    ///
+    /// # Returns
+    ///
+    /// * The resolved `Config`.
+    ///
+    /// # Errors
+    ///
+    /// * If the configuration file is invalid, see [`Config::from_file`].
+    /// * If the run manifest is invalid, see [`Manifest::load`].
+    /// * If the environment holds an invalid value, see [`Config::from_env`].
+    /// * If the resolved configuration is invalid, see [`Config::validate`].
+    pub fn from_args() -> Result<Self> {
+        let mut args = Args::parse();
+        let mut config = match (&args.config, &args.from_manifest) {
+            (Some(path), _) => Self::from_file(path)?,
+            (None, Some(path)) => Manifest::load(path)?.into_config()?,
+            (None, None) => Self::default(),
+        };
+
+        config.apply_env()?;
+        config.command = args.command.take();
+        config.manifest = args.manifest.take();
+
+        let quiet = args.quiet;
+        let mut layer = FileConfig::from(args);
+        if quiet {
+            layer.log_level = Some(LogLevel::Warn);
+        }
+
+        config.apply(layer);
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Check that the configuration values are usable.
+    ///
+    /// # Errors
+    ///
+    /// * If the `topic_prefix` is empty, or holds characters not allowed in topic names.
+    /// * If the `rate` or either rate of the `ramp` isn't a positive, finite number.
+    /// * If the `max_bytes` is zero.
+    /// * If the `batch_size` is zero, or larger than `max_inflight`.
+    /// * If the number of `workers` is zero, or above one while replaying or ingesting.
+    /// * If the `error_threshold` is zero.
+    /// * If both a file to `replay` and one to `ingest_csv` are given.
+    /// * If `validate_only` is set without a file to `replay` or `ingest_csv`.
+    /// * If `replay_realtime` or a `time_scale` is given without a file to `replay`.
+    /// * If the number of `partitions` or the `key_distribution` isn't positive.
+    /// * If the number of `partitions` is given without keying by customer.
+    /// * If the number of `topic_shards` is zero.
+    /// * If the `replication_factor` isn't positive.
+    /// * If the `poll_interval` is zero.
+    /// * If the `customer_range` is empty or includes zero.
+    /// * If the `max_consumption` isn't a positive, finite number.
+    /// * If the parameters of the `model` are invalid.
+    /// * If the `skew` or the `time_scale` is negative or not finite.
+    /// * If a `skew` is given with a `customer_mode` other than random.
+    /// * If the number of `meters` is zero, or exceeds the customers in the `customer_range`.
+    /// * If a `schema_registry_url` is given, but the `format` isn't Avro.
+    /// * If `extra_fields` are given, but the `format` isn't JSON, or one is named like a field of
+    ///   the message.
+    /// * If `acks` other than `all` are given with exactly-once delivery.
+    /// * If SASL settings are given without a SASL `security_protocol`, or are incomplete.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(prefix) = &self.topic_prefix {
+            if prefix.is_empty()
+                || !prefix
+                    .chars()
+                    .all(|x| x.is_ascii_alphanumeric() || matches!(x, '.' | '_' | '-'))
+            {
+                bail!(
+                    "Topic prefix must only hold letters, digits, '.', '_' and '-', got {prefix:?}!"
+                );
+            }
+        }
+
+        if let Some(rate) = self.rate {
+            if !rate.is_finite() || rate <= 0.0 {
+                bail!("Rate must be a positive number, got {rate}!");
+            }
+        }
+
+        if let Some(ramp) = self.ramp {
+            for rate in [ramp.start_rate, ramp.end_rate] {
+                if !rate.is_finite() || rate <= 0.0 {
+                    bail!("Ramp rates must be positive numbers, got {rate}!");
+                }
+            }
+        }
+
+        if self.max_bytes == 0 {
+            bail!("Max bytes must be at least 1!");
+        }
+
+        if self.batch_size == 0 {
+            bail!("Batch size must be at least 1!");
+        }
+
+        if self.batch_size > self.max_inflight as usize {
+            bail!(
+                "Batch size must not exceed the {} messages allowed in flight, got {}!",
+                self.max_inflight,
+                self.batch_size,
+            );
+        }
+
+        if self.workers == 0 {
+            bail!("Worker count must be at least 1!");
+        }
+
+        if self.workers > 1 && (self.replay.is_some() || self.ingest_csv.is_some()) {
+            bail!(
+                "Replaying or ingesting requires a single worker, got {}!",
+                self.workers
+            );
+        }
+
+        if self.error_threshold == Some(0) {
+            bail!("Error threshold must be at least 1!");
+        }
+
+        if self.replay.is_some() && self.ingest_csv.is_some() {
+            bail!("Only one of replaying and ingesting a CSV file is allowed!");
+        }
+
+        if self.validate_only && self.replay.is_none() && self.ingest_csv.is_none() {
+            bail!("Validating requires a file to replay or ingest!");
+        }
+
+        if (self.replay_realtime || self.time_scale.is_some()) && self.replay.is_none() {
+            bail!("Pacing the replay requires a file to replay!");
+        }
+
+        if let Some(partitions) = self.partitions {
+            if partitions <= 0 {
+                bail!("Partition count must be positive, got {partitions}!");
+            }
+
+            if !self.keying.is_keyed() {
+                bail!("Pinning customers to partitions requires keyed messages!");
+            }
+        }
+
+        if let Some(partitions) = self.key_distribution {
+            if partitions <= 0 {
+                bail!("Key distribution partition count must be positive, got {partitions}!");
+            }
+        }
+
+        if self.topic_shards == 0 {
+            bail!("Topic shard count must be at least 1!");
+        }
+
+        if let Some(replication_factor) = self.replication_factor {
+            if replication_factor <= 0 {
+                bail!("Replication factor must be positive, got {replication_factor}!");
+            }
+        }
+
+        if self.poll_interval == Some(0) {
+            bail!("Poll interval must be at least 1 ms!");
+        }
+
+        let (min, max) = (*self.customer_range.start(), *self.customer_range.end());
+        if min == 0 || min > max {
+            bail!("Customer range must satisfy 0 < min <= max, got {min}-{max}!");
+        }
+
+        if !self.max_consumption.is_finite() || self.max_consumption <= 0.0 {
+            bail!(
+                "Max consumption must be a positive number, got {} mWh!",
+                self.max_consumption
+            );
+        }
+
+        if let Some(model) = &self.model {
+            model.validate()?;
+        }
+
+        if !self.skew.is_finite() || self.skew < 0.0 {
+            bail!("Skew must be a non-negative number, got {}!", self.skew);
+        }
+
+        if self.skew > 0.0 && self.customer_mode != CustomerMode::Random {
+            bail!("Skew requires the random customer mode!");
+        }
+
+        if let Some(time_scale) = self.time_scale {
+            if !time_scale.is_finite() || time_scale < 0.0 {
+                bail!("Time scale must be a non-negative number, got {time_scale}!");
+            }
+        }
+
+        if let Some(meters) = self.meters {
+            let customers = u64::from(max - min) + 1;
+            if meters == 0 || meters as u64 > customers {
+                bail!("Meter count must be in 1..={customers}, got {meters}!");
+            }
+        }
+
+        if self.delivery_guarantee == Some(DeliveryGuarantee::ExactlyOnce)
+            && self.acks.is_some_and(|x| x != Acks::All)
+        {
+            bail!("Exactly-once delivery requires acks from all in-sync replicas!");
+        }
+
+        let is_sasl = self
+            .security_protocol
+            .is_some_and(SecurityProtocol::is_sasl);
+        let has_sasl = self.sasl_mechanism.is_some()
+            || self.sasl_username.is_some()
+            || self.sasl_password.is_some();
+        if has_sasl && !is_sasl {
+            bail!("SASL settings require a security protocol of sasl-plaintext or sasl-ssl!");
+        }
+
+        if is_sasl
+            && (self.sasl_mechanism.is_none()
+                || self.sasl_username.is_none()
+                || self.sasl_password.is_none())
+        {
+            bail!("SASL requires a mechanism, a username and a password!");
+        }
+
+        #[cfg(feature = "schema-registry")]
+        if self.schema_registry_url.is_some() && self.format != Format::Avro {
+            bail!("The schema registry requires the Avro format!");
+        }
+
+        if !self.extra_fields.is_empty() && self.format != Format::Json {
+            bail!("Extra fields require the JSON format!");
+        }
+
+        if let Some(name) = self
+            .extra_fields
+            .keys()
+            .find(|x| MESSAGE_FIELDS.contains(&x.as_str()))
+        {
+            bail!("Extra field {name:?} is already a field of the message!");
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the configuration from the environment, falling back to the defaults.
+    ///
+    /// # Returns
+    ///
+    /// * The resolved `Config`.
+    ///
+    /// # Errors
+    ///
+    /// * If `KAFKA_BROKERS` is set but holds no brokers.
+    /// * If any variable is set but isn't valid unicode.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::default();
+        config.apply_env()?;
+
+        Ok(config)
+    }
+
+    /// Resolve the configuration from a TOML file, falling back to the defaults.
+    ///
+    /// The keys are named like the fields of the `Config`, see `config.example.toml`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// * The resolved `Config`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be read.
+    /// * If the file isn't valid TOML, or holds unknown keys or invalid values.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}!", path.display()))?;
+
+        Self::from_toml(&contents).with_context(|| format!("Invalid config in {}!", path.display()))
+    }
+
+    /// Resolve the configuration from the contents of a TOML file, falling back to the defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The TOML document.
+    ///
+    /// # Returns
+    ///
+    /// * The resolved `Config`.
+    ///
+    /// # Errors
+    ///
+    /// * If the document isn't valid TOML, or holds unknown keys or invalid values.
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let file: FileConfig = toml::from_str(contents)?;
+
+        Ok(Self::from_file_config(file))
+    }
+
+    /// Resolve the configuration from a JSON object keyed like the configuration file, as written
+    /// by [`Config::to_json`], falling back to the defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The JSON object.
+    ///
+    /// # Returns
+    ///
+    /// * The resolved `Config`.
+    ///
+    /// # Errors
+    ///
+    /// * If the object holds unknown keys or invalid values.
+    pub fn from_json(value: serde_json::Value) -> Result<Self> {
+        let file: FileConfig = serde_json::from_value(value)?;
+
+        Ok(Self::from_file_config(file))
+    }
+
+    /// Encode the configuration as a JSON object keyed like the configuration file, leaving out
+    /// the commands and credentials, so that a run can be repeated with [`Config::from_json`].
+    ///
+    /// # Returns
+    ///
+    /// * The JSON object.
+    ///
+    /// # Errors
+    ///
+    /// * If a value can't be represented in JSON.
+    pub fn to_json(&self) -> serde_json::Result<serde_json::Value> {
+        // Keep credentials passed as client properties out of the file, like out of the logs.
+        let mut file = self.to_file_config();
+        file.kafka_config
+            .retain(|key, _| !key.contains("password") && !key.contains("secret"));
+
+        serde_json::to_value(file)
+    }
+
+    /// Resolve the configuration from the values of a file, falling back to the defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The values of the file.
+    ///
+    /// # Returns
+    ///
+    /// * The resolved `Config`.
+    fn from_file_config(file: FileConfig) -> Self {
+        let mut config = Self::default();
+        config.apply(file);
+
+        config
+    }
+
+    /// Override the configuration with the values of the environment.
+    ///
+    /// # Errors
+    ///
+    /// * If `KAFKA_BROKERS` is set but holds no brokers.
+    /// * If any variable is set but isn't valid unicode.
+    fn apply_env(&mut self) -> Result<()> {
+        if let Some(brokers) = read_var(BROKERS_VAR)? {
+            self.brokers = parse_brokers(&brokers)
+                .with_context(|| format!("Invalid value for {BROKERS_VAR}!"))?;
+        }
+
+        if let Some(topic) = read_var(TOPIC_VAR)? {
+            let topic = topic.trim();
+            if !topic.is_empty() {
+                self.topic = topic.to_string();
+            }
+        }
+
+        if let Some(password) = read_var(SASL_PASSWORD_VAR)? {
+            self.sasl_password = Some(password);
+        }
+
+        Ok(())
+    }
+
+    /// Get the topic to produce to, namespaced by the `topic_prefix`.
+    ///
+    /// # Returns
+    ///
+    /// * The topic formatted as `{topic_prefix}.{topic}`, or just the `topic` without a prefix.
+    #[must_use]
+    pub fn full_topic(&self) -> String {
+        match &self.topic_prefix {
+            Some(prefix) => format!("{prefix}.{}", self.topic),
+            None => self.topic.clone(),
+        }
+    }
+
+    /// Get every topic to produce to, one per shard.
+    ///
+    /// # Returns
+    ///
+    /// * The names of the topics, see [`topic_for`].
+    #[must_use]
+    pub fn topics(&self) -> Vec<String> {
+        let topic = self.full_topic();
+
+        (0..self.topic_shards.max(1))
+            .map(|shard| topic_for(&topic, shard, self.topic_shards))
+            .collect()
+    }
+
+    /// Collect the properties of the Kafka client, with the extra `kafka_config` taking precedence
+    /// over the keying, the delivery guarantee, the `acks`, the `linger_ms` and the security
+    /// settings.
+    ///
+    /// # Returns
+    ///
+    /// * The client properties, by key.
+    #[must_use]
+    pub fn client_properties(&self) -> HashMap<String, String> {
+        let mut properties = HashMap::new();
+        for (key, value) in self.keying.properties() {
+            properties.insert((*key).to_string(), (*value).to_string());
+        }
+
+        if let Some(guarantee) = self.delivery_guarantee {
+            for (key, value) in guarantee.properties() {
+                properties.insert((*key).to_string(), (*value).to_string());
+            }
+        }
+
+        if let Some(acks) = self.acks {
+            properties.insert("acks".to_string(), acks.as_str().to_string());
+        }
+
+        if let Some(linger_ms) = self.linger_ms {
+            properties.insert("queue.buffering.max.ms".to_string(), linger_ms.to_string());
+        }
+
+        if let Some(protocol) = self.security_protocol {
+            properties.insert(
+                "security.protocol".to_string(),
+                protocol.as_str().to_string(),
+            );
+        }
+
+        if let Some(mechanism) = self.sasl_mechanism {
+            properties.insert("sasl.mechanism".to_string(), mechanism.as_str().to_string());
+        }
+
+        if let Some(username) = &self.sasl_username {
+            properties.insert("sasl.username".to_string(), username.clone());
+        }
+
+        if let Some(password) = &self.sasl_password {
+            properties.insert("sasl.password".to_string(), password.clone());
+        }
+
+        properties.extend(self.kafka_config.clone());
+
+        properties
+    }
+}
+
+/// Split a comma-separated list of brokers, trimming the whitespace around each of them.
+///
+/// # Arguments
+///
+/// * `brokers` - The comma-separated list of brokers.
+///
+/// # Returns
+///
+/// * The individual brokers.
+///
+/// # Errors
+///
+/// * If the list holds no brokers.
+pub fn parse_brokers(brokers: &str) -> Result<Vec<String>> {
+    let brokers: Vec<String> = brokers
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToString::to_string)
+        .collect();
+    if brokers.is_empty() {
+        bail!("Expected at least one broker!");
+    }
+
+    Ok(brokers)
+}
+
+/// Parse an inclusive range of customer IDs, formatted as `<min>-<max>`.
+///
+/// # Arguments
+///
+/// * `range` - The formatted range, e.g. `1-500000`.
+///
+/// # Returns
+///
+/// * The parsed range.
+///
+/// # Errors
+///
+/// * If the range isn't formatted as `<min>-<max>`.
+pub fn parse_customer_range(range: &str) -> Result<RangeInclusive<u32>> {
+    let (min, max) = range
+        .split_once('-')
+        .context("Expected a range formatted as <min>-<max>!")?;
+    let min = min
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid minimum customer ID: {min:?}!"))?;
+    let max = max
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid maximum customer ID: {max:?}!"))?;
+
+    Ok(min..=max)
+}
+
+/// Parse a ramp of the rate, formatted as `<start>:<end>:<seconds>`.
+///
+/// # Arguments
+///
+/// * `ramp` - The formatted ramp, e.g. `100:10000:60`.
+///
+/// # Returns
+///
+/// * The parsed ramp.
+///
+/// # Errors
+///
+/// * If the ramp isn't formatted as `<start>:<end>:<seconds>`.
+pub fn parse_ramp(ramp: &str) -> Result<RateRamp> {
+    let fields: Vec<&str> = ramp.split(':').map(str::trim).collect();
+    let [start, end, seconds] = fields[..] else {
+        bail!("Expected a ramp formatted as <start>:<end>:<seconds>!");
+    };
+
+    Ok(RateRamp::new(
+        start
+            .parse()
+            .with_context(|| format!("Invalid start rate: {start:?}!"))?,
+        end.parse()
+            .with_context(|| format!("Invalid end rate: {end:?}!"))?,
+        Duration::from_secs(
+            seconds
+                .parse()
+                .with_context(|| format!("Invalid ramp duration: {seconds:?}!"))?,
+        ),
+    ))
+}
+
+/// Parse a Kafka client property, formatted as `<key>=<value>`.
+///
+/// # Arguments
+///
+/// * `property` - The formatted property, e.g. `compression.type=lz4`.
+///
+/// # Returns
+///
+/// * The key and value of the property.
+///
+/// # Errors
+///
+/// * If the property isn't formatted as `<key>=<value>`, or the key is empty.
+pub fn parse_key_value(property: &str) -> Result<(String, String)> {
+    let (key, value) = property
+        .split_once('=')
+        .context("Expected a property formatted as <key>=<value>!")?;
+    let key = key.trim();
+    if key.is_empty() {
+        bail!("Property key must not be empty!");
+    }
+
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Deserialize an optional range of customer IDs, formatted as `<min>-<max>`.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer of the formatted range.
+///
+/// # Returns
+///
+/// * The parsed range.
+///
+/// # Errors
+///
+/// * If the range isn't formatted as `<min>-<max>`, see [`parse_customer_range`].
+fn deserialize_customer_range<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<RangeInclusive<u32>>, D::Error> {
+    let range = String::deserialize(deserializer)?;
+
+    parse_customer_range(&range)
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+}
+
+/// Serialize an optional inclusive range of customer IDs, formatted as `<min>-<max>`.
+///
+/// # Arguments
+///
+/// * `range` - The range to serialize, skipped if `None`.
+/// * `serializer` - The serializer of the formatted range.
+///
+/// # Errors
+///
+/// * If the formatted range couldn't be serialized.
+fn serialize_customer_range<S: Serializer>(
+    range: &Option<RangeInclusive<u32>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match range {
+        Some(range) => serializer.serialize_str(&format!("{}-{}", range.start(), range.end())),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serialize an optional ramp of the rate, formatted as `<start>:<end>:<seconds>`.
+///
+/// # Arguments
+///
+/// * `ramp` - The ramp to serialize, skipped if `None`.
+/// * `serializer` - The serializer of the formatted ramp.
+///
+/// # Errors
+///
+/// * If the formatted ramp couldn't be serialized.
+fn serialize_ramp<S: Serializer>(
+    ramp: &Option<RateRamp>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match ramp {
+        Some(ramp) => serializer.serialize_str(&format!(
+            "{}:{}:{}",
+            ramp.start_rate,
+            ramp.end_rate,
+            ramp.duration.as_secs(),
+        )),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserialize an optional ramp of the rate, formatted as `<start>:<end>:<seconds>`.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer of the formatted ramp.
+///
+/// # Returns
+///
+/// * The parsed ramp.
+///
+/// # Errors
+///
+/// * If the ramp isn't formatted as `<start>:<end>:<seconds>`, see [`parse_ramp`].
+fn deserialize_ramp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<RateRamp>, D::Error> {
+    let ramp = String::deserialize(deserializer)?;
+
+    parse_ramp(&ramp)
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+}
+
+/// Read an environment variable, treating an unset variable as `None`.
+///
+/// # Arguments
+///
+/// * `key` - The name of the variable.
+///
+/// # Returns
+///
+/// * The value of the variable, if set.
+///
+/// # Errors
+///
+/// * If the variable is set but isn't valid unicode.
+fn read_var(key: &str) -> Result<Option<String>> {
+    match env::var(key) {
+        Ok(v) => Ok(Some(v)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {key}!")),
+    }
+}
+
+/// The configuration of the consumer.
+///
+/// # Fields
+///
+/// * `brokers` - The Kafka brokers to connect to.
+/// * `topic` - The topic to consume messages from.
+/// * `group_id` - The consumer group to join, shared between instances to split the load.
+/// * `report_every` - The number of messages between reports of the top customers, `0` disables
+///   them.
+/// * `top` - The number of customers to report.
+/// * `commit_mode` - How the offsets of the consumed messages are committed.
+/// * `output_topic` - The topic to produce the sums of every window to, not aggregating windows
+///   if `None`.
+/// * `window` - The length of each window, in seconds.
+/// * `grace` - How long after its end a window still accepts late messages, in seconds.
+#[derive(Debug, Clone, Parser)]
+#[command(version, about = "Consume and print messages from a Kafka topic.")]
+pub struct ConsumerConfig {
+    /// Comma-separated list of Kafka brokers.
+    #[arg(long, value_delimiter = ',', default_values_t = default_brokers())]
+    pub brokers: Vec<String>,
+
+    /// The topic to consume messages from.
+    #[arg(long, default_value = DEFAULT_TOPIC)]
+    pub topic: String,
+
+    /// The consumer group to join.
+    #[arg(long, default_value = DEFAULT_GROUP_ID)]
+    pub group_id: String,
+
+    /// The number of messages between reports of the top customers, `0` disables them.
+    #[arg(long, default_value_t = 1_000)]
+    pub report_every: u64,
+
+    /// The number of customers with the highest total consumption to report.
+    #[arg(long, default_value_t = 10)]
+    pub top: usize,
+
+    /// How to commit the offsets of the consumed messages, `manual` to only commit them once
+    /// they have been processed.
+    #[arg(long, value_enum, default_value_t)]
+    pub commit_mode: CommitMode,
+
+    /// The topic to produce the consumption of every customer over each window to.
+    #[arg(long)]
+    pub output_topic: Option<String>,
+
+    /// The length of each window, in seconds.
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub window: u64,
+
+    /// How long after its end a window still accepts late messages, in seconds.
+    #[arg(long, default_value_t = 10)]
+    pub grace: u64,
+
+    /// The most times to retry a window sum failing with a transient error, like a timeout,
+    /// before keeping it for the next flush.
+    #[arg(long, default_value_t = DEFAULT_MAX_RETRIES)]
+    pub max_retries: u32,
+
+    /// The number of milliseconds to wait before the first retry of a window sum, doubled on
+    /// every retry.
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_RETRY_BASE_DELAY)]
+    pub retry_base_delay: u64,
+}
+
+impl ConsumerConfig {
+    /// Parse the configuration from the command-line arguments.
+    ///
+    /// Exits the process with a usage message if the arguments are invalid.
+    ///
+    /// # Returns
+    ///
+    /// * The parsed `ConsumerConfig`.
+    #[must_use]
+    pub fn from_args() -> Self {
+        Self::parse()
+    }
+}
+
+/// Get the default brokers as owned strings.
+fn default_brokers() -> Vec<String> {
+    DEFAULT_BROKERS.iter().map(|x| (*x).to_string()).collect()
+}
@@ -0,0 +1,102 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+/// Extra fields attached to a message, by name.
+pub type ExtraFields = BTreeMap<String, serde_json::Value>;
+
+/// A message along with extra fields, serialized alongside its core fields, e.g.
+/// `{"customer_id":1042,"consumption":6.5,"timestamp":1700000000000,"region":"north"}`.
+///
+/// The extension may be any type serializing as a map, like [`ExtraFields`] or a struct, so
+/// that new dimensions can be tried without changing the `Message` itself.
+///
+/// # Fields
+///
+/// * `message` - The enriched message.
+/// * `extra` - The extra fields, which mustn't share a name with a field of the `message`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Enriched<T = ExtraFields> {
+    #[serde(flatten)]
+    message: Message,
+    #[serde(flatten)]
+    extra: T,
+}
+
+impl<T> Enriched<T> {
+    /// Construct a new `Enriched` message.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to enrich.
+    /// * `extra` - The extra fields of the message.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Enriched`.
+    #[must_use]
+    pub const fn new(message: Message, extra: T) -> Self {
+        Self { message, extra }
+    }
+
+    /// Get the enriched message.
+    ///
+    /// # Returns
+    ///
+    /// * A reference to the message.
+    #[must_use]
+    pub const fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Get the extra fields of the message.
+    ///
+    /// # Returns
+    ///
+    /// * A reference to the extra fields.
+    #[must_use]
+    pub const fn extra(&self) -> &T {
+        &self.extra
+    }
+}
+
+impl<T: Serialize> Enriched<T> {
+    /// Encode the enriched message as JSON.
+    ///
+    /// # Returns
+    ///
+    /// * The JSON object.
+    ///
+    /// # Errors
+    ///
+    /// * If the extra fields don't serialize as a map.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+/// A source of extra fields for messages before they're sent, see [`Enriched`].
+pub trait Enricher: Debug + Send + Sync {
+    /// Compute the extra fields of a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to enrich.
+    ///
+    /// # Returns
+    ///
+    /// * The extra fields, empty to send the message as is.
+    fn enrich(&self, message: &Message) -> ExtraFields;
+}
+
+/// An enricher attaching the same fields to every message, e.g. the region of a deployment.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StaticFields(pub ExtraFields);
+
+impl Enricher for StaticFields {
+    fn enrich(&self, _message: &Message) -> ExtraFields {
+        self.0.clone()
+    }
+}
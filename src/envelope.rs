@@ -0,0 +1,126 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::EnvelopeError;
+
+/// The version of the payloads produced by this version of the crate.
+pub const CURRENT_VERSION: u8 = 1;
+
+/// The version of an envelope, decoded before its payload so that unknown versions are told apart
+/// from malformed payloads.
+///
+/// # Fields
+///
+/// * `version` - The version of the payload.
+#[derive(Deserialize)]
+struct Header {
+    version: u8,
+}
+
+/// A payload wrapped along with the version of its format, so that consumers can branch on the
+/// version as the format evolves.
+///
+/// Envelopes are encoded as JSON objects, e.g. `{"version":1,"payload":{...}}`. They're meant for
+/// payloads stored or passed on outside of Kafka: the records produced to Kafka are left
+/// unwrapped, carrying the version in their
+/// [`SCHEMA_VERSION_HEADER`](crate::format::SCHEMA_VERSION_HEADER) instead, so that every wire
+/// format is versioned and not only JSON.
+///
+/// # Fields
+///
+/// * `version` - The version of the payload's format.
+/// * `payload` - The wrapped payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    version: u8,
+    payload: T,
+}
+
+impl<T> Envelope<T> {
+    /// Wrap a payload in the [`CURRENT_VERSION`].
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload to wrap.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Envelope`.
+    #[must_use]
+    pub const fn new(payload: T) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            payload,
+        }
+    }
+
+    /// Get the version of the payload's format.
+    ///
+    /// # Returns
+    ///
+    /// * The version.
+    #[must_use]
+    pub const fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Get the wrapped payload.
+    ///
+    /// # Returns
+    ///
+    /// * A reference to the payload.
+    #[must_use]
+    pub const fn payload(&self) -> &T {
+        &self.payload
+    }
+
+    /// Unwrap the payload.
+    ///
+    /// # Returns
+    ///
+    /// * The payload.
+    #[must_use]
+    pub fn into_payload(self) -> T {
+        self.payload
+    }
+}
+
+impl<T: Serialize> Envelope<T> {
+    /// Encode the envelope as JSON.
+    ///
+    /// # Returns
+    ///
+    /// * The encoded bytes.
+    ///
+    /// # Errors
+    ///
+    /// * If the payload couldn't be serialized.
+    pub fn encode(&self) -> Result<Vec<u8>, EnvelopeError> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+impl<T: DeserializeOwned> Envelope<T> {
+    /// Decode an envelope from JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The encoded envelope.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded envelope.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes aren't a JSON object with a `version`.
+    /// * If the version isn't supported by this version of the crate.
+    /// * If the payload doesn't match its version.
+    pub fn decode(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let Header { version } = serde_json::from_slice(bytes)?;
+        if version != CURRENT_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
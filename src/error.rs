@@ -0,0 +1,188 @@
+use rdkafka::{error::KafkaError, types::RDKafkaErrorCode};
+use thiserror::Error;
+
+/// An error raised when a `Message` holds values that make no physical sense.
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum MessageError {
+    /// The consumption is below zero.
+    #[error("Consumption must not be negative, got {0} mWh!")]
+    NegativeConsumption(f32),
+
+    /// The consumption is either NaN or infinite.
+    #[error("Consumption must be finite, got {0} mWh!")]
+    NonFiniteConsumption(f32),
+
+    /// The customer ID is zero.
+    #[error("Customer ID must not be zero!")]
+    ZeroCustomerId,
+
+    /// The customer ID is outside of the range allowed by the schema.
+    #[error("Customer ID must have exactly 4 digits, got {0}!")]
+    CustomerIdOutOfRange(u32),
+
+    /// The time window to pick a timestamp from ends before it starts.
+    #[error("Time window must not end before it starts, got {start}..{end} ms!")]
+    InvalidWindow { start: u128, end: u128 },
+
+    /// A required field was never set on a `MessageBuilder`.
+    #[error("Missing required field: {0}!")]
+    MissingField(&'static str),
+
+    /// The consumption couldn't be sampled from a `ConsumptionModel`.
+    #[error("Invalid consumption model: {0}")]
+    Model(#[from] ModelError),
+
+    /// The timestamp couldn't be read from a `Clock`.
+    #[error("Failed to read the clock: {0}")]
+    Clock(#[from] ClockError),
+}
+
+/// An error raised when a `Message` couldn't be parsed from its one-line string form.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseMessageError {
+    /// A field isn't formatted as `<key>=<value>`.
+    #[error("Expected a field formatted as <key>=<value>, got {0:?}!")]
+    MalformedField(String),
+
+    /// A field is named neither `customer`, `consumption` nor `ts`.
+    #[error("Unknown field: {0:?}!")]
+    UnknownField(String),
+
+    /// A field is given more than once.
+    #[error("Duplicate field: {0}!")]
+    DuplicateField(&'static str),
+
+    /// A required field is missing.
+    #[error("Missing required field: {0}!")]
+    MissingField(&'static str),
+
+    /// The value of a field couldn't be parsed.
+    #[error("Invalid value for {field}: {value:?}!")]
+    InvalidValue { field: &'static str, value: String },
+}
+
+/// An error raised when a `ConsumptionModel` has invalid parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum ModelError {
+    /// The uniform range holds no values.
+    #[error("Uniform range must not be empty, got {min}..{max} mWh!")]
+    EmptyRange { min: f32, max: f32 },
+
+    /// The standard deviation is either negative or not finite.
+    #[error("Standard deviation must be finite and non-negative, got {0} mWh!")]
+    InvalidStandardDeviation(f32),
+
+    /// The rate of the exponential distribution isn't positive.
+    #[error("Rate must be positive, got {0}!")]
+    InvalidRate(f32),
+}
+
+/// An error raised when a `Clock` can't tell the time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ClockError {
+    /// The time is before the Unix Epoch, and so can't be represented as a timestamp.
+    #[error("Time is before the Unix Epoch!")]
+    BeforeEpoch,
+}
+
+/// An error raised when a `MessageSource` is configured with invalid parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum SourceError {
+    /// The exponent of the customer skew is either negative or not finite.
+    #[error("Skew must be a non-negative number, got {0}!")]
+    InvalidSkew(f64),
+
+    /// The time scale of a replay isn't a positive, finite number.
+    #[error("Time scale must be a positive number, got {0}!")]
+    InvalidTimeScale(f64),
+}
+
+/// An error raised when an `Envelope` couldn't be encoded or decoded.
+#[derive(Debug, Error)]
+pub enum EnvelopeError {
+    /// The envelope isn't valid JSON, or doesn't match the shape of its version.
+    #[error("Malformed envelope: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The envelope has a version this version of the crate doesn't know of.
+    #[error("Unsupported envelope version: {0}!")]
+    UnsupportedVersion(u8),
+}
+
+/// An error raised when a `Message` couldn't be produced.
+#[derive(Debug, Clone, Error)]
+pub enum ProducerError {
+    /// The queue of the Kafka client is full, the message may be retried once it drains.
+    #[error("Producer queue is full!")]
+    QueueFull,
+
+    /// The Kafka client can no longer produce any messages.
+    #[error("Fatal Kafka Error: {0}")]
+    Fatal(KafkaError),
+
+    /// The message couldn't be encoded.
+    #[error("Failed to serialize message: {0}")]
+    Serialization(String),
+
+    /// The encoded message is larger than the brokers would accept.
+    #[error("Payload of {size} bytes exceeds the limit of {limit} bytes!")]
+    PayloadTooLarge { size: usize, limit: usize },
+
+    /// The message couldn't be delivered, without affecting other messages.
+    #[error("Kafka Error: {0}")]
+    Delivery(KafkaError),
+}
+
+impl ProducerError {
+    /// Check whether the producer should stop, as no other message can be produced either.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the error is fatal.
+    #[must_use]
+    pub const fn is_fatal(&self) -> bool {
+        matches!(self, Self::Fatal(_))
+    }
+
+    /// Check whether sending the message again may succeed, as the error is transient, like a
+    /// timeout or a leader election.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the error is retryable.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::QueueFull => true,
+            Self::Fatal(_) | Self::Serialization(_) | Self::PayloadTooLarge { .. } => false,
+            Self::Delivery(e) => matches!(
+                e.rdkafka_error_code(),
+                Some(
+                    RDKafkaErrorCode::BrokerTransportFailure
+                        | RDKafkaErrorCode::MessageTimedOut
+                        | RDKafkaErrorCode::AllBrokersDown
+                        | RDKafkaErrorCode::OperationTimedOut
+                        | RDKafkaErrorCode::LeaderNotAvailable
+                        | RDKafkaErrorCode::NotLeaderForPartition
+                        | RDKafkaErrorCode::RequestTimedOut
+                        | RDKafkaErrorCode::BrokerNotAvailable
+                        | RDKafkaErrorCode::NetworkException
+                        | RDKafkaErrorCode::NotEnoughReplicas
+                        | RDKafkaErrorCode::NotEnoughReplicasAfterAppend
+                        | RDKafkaErrorCode::KafkaStorageError
+                )
+            ),
+        }
+    }
+}
+
+impl From<KafkaError> for ProducerError {
+    fn from(error: KafkaError) -> Self {
+        match error.rdkafka_error_code() {
+            Some(RDKafkaErrorCode::QueueFull) => Self::QueueFull,
+            Some(RDKafkaErrorCode::Fatal) => Self::Fatal(error),
+            _ if matches!(error, KafkaError::Canceled) => Self::Fatal(error),
+            _ => Self::Delivery(error),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::LazyLock;
+
+use anyhow::{bail, Context, Result};
+use apache_avro::{from_avro_datum, to_avro_datum, types::Value, Schema};
+
+use crate::{Message, MilliwattHours};
+
+/// The Avro schema of a `Message`, as registered with a schema registry.
+pub const AVRO_SCHEMA: &str = r#"{
+  "type": "record",
+  "name": "Message",
+  "namespace": "tech.asmussen.h4_bigdata",
+  "fields": [
+    { "name": "customer_id", "type": "int", "doc": "The ID of the customer." },
+    { "name": "consumption", "type": "float", "doc": "The mWh of the customer's electrical consumption." },
+    { "name": "timestamp", "type": "long", "doc": "The time, in milliseconds since the Unix Epoch." }
+  ]
+}"#;
+
+/// The parsed form of [`AVRO_SCHEMA`].
+static SCHEMA: LazyLock<Schema> =
+    LazyLock::new(|| Schema::parse_str(AVRO_SCHEMA).expect("Avro schema should be valid!"));
+
+impl Message {
+    /// Encode the message as a single Avro datum, without an embedded schema.
+    ///
+    /// # Returns
+    ///
+    /// * The Avro datum.
+    ///
+    /// # Errors
+    ///
+    /// * If the customer ID doesn't fit in an Avro `int`.
+    /// * If the timestamp doesn't fit in an Avro `long`.
+    pub fn to_avro(&self) -> Result<Vec<u8>> {
+        let customer_id =
+            i32::try_from(self.customer_id).context("Customer ID doesn't fit in an Avro int!")?;
+        let timestamp =
+            i64::try_from(self.timestamp).context("Timestamp doesn't fit in an Avro long!")?;
+
+        let record = Value::Record(vec![
+            ("customer_id".to_string(), Value::Int(customer_id)),
+            ("consumption".to_string(), Value::Float(self.consumption.0)),
+            ("timestamp".to_string(), Value::Long(timestamp)),
+        ]);
+
+        Ok(to_avro_datum(&SCHEMA, record)?)
+    }
+
+    /// Decode a message from a single Avro datum, written with [`AVRO_SCHEMA`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The Avro datum.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes don't hold a valid message.
+    pub fn from_avro(mut bytes: &[u8]) -> Result<Self> {
+        let Value::Record(fields) = from_avro_datum(&SCHEMA, &mut bytes, None)? else {
+            bail!("Expected an Avro record!");
+        };
+
+        let (mut customer_id, mut consumption, mut timestamp) = (None, None, None);
+        for (name, value) in fields {
+            match (name.as_str(), value) {
+                ("customer_id", Value::Int(v)) => customer_id = Some(u32::try_from(v)?),
+                ("consumption", Value::Float(v)) => consumption = Some(MilliwattHours(v)),
+                ("timestamp", Value::Long(v)) => timestamp = Some(u128::try_from(v)?),
+                (name, value) => bail!("Unexpected Avro field {name}: {value:?}!"),
+            }
+        }
+
+        Ok(Self::new(
+            customer_id.context("Missing Avro field customer_id!")?,
+            consumption.context("Missing Avro field consumption!")?,
+            timestamp.context("Missing Avro field timestamp!")?,
+        ))
+    }
+}
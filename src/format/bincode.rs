@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use crate::Message;
+
+impl Message {
+    /// Encode the message with [bincode](https://github.com/bincode-org/bincode).
+    ///
+    /// # Returns
+    ///
+    /// * The compact binary encoding.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be serialized.
+    pub fn to_bincode(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Decode a message encoded with [bincode](https://github.com/bincode-org/bincode).
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The compact binary encoding.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes don't hold a valid message.
+    pub fn from_bincode(bytes: &[u8]) -> Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Context, Result};
+
+use crate::{Message, MilliwattHours};
+
+/// The header line naming the columns of [`Message::to_csv_record`].
+pub const CSV_HEADER: &str = "customer_id,consumption_mwh,timestamp";
+
+impl Message {
+    /// Encode the message as a CSV row, with the columns of [`CSV_HEADER`].
+    ///
+    /// # Returns
+    ///
+    /// * The CSV row, without a trailing newline.
+    #[must_use]
+    pub fn to_csv_record(&self) -> String {
+        format!(
+            "{},{},{}",
+            self.customer_id, self.consumption.0, self.timestamp
+        )
+    }
+
+    /// Decode a message from a CSV row, with the columns of [`CSV_HEADER`].
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The CSV row, optionally with a trailing newline.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the row doesn't have exactly three fields.
+    /// * If a field couldn't be parsed.
+    pub fn from_csv_record(record: &str) -> Result<Self> {
+        let fields: Vec<&str> = record.trim_end().split(',').map(str::trim).collect();
+        let [customer_id, consumption, timestamp] = fields[..] else {
+            bail!("Expected 3 fields, got {}!", fields.len());
+        };
+
+        Ok(Self::new(
+            customer_id
+                .parse()
+                .with_context(|| format!("Invalid customer ID: {customer_id:?}!"))?,
+            MilliwattHours(
+                consumption
+                    .parse()
+                    .with_context(|| format!("Invalid consumption: {consumption:?}!"))?,
+            ),
+            timestamp
+                .parse()
+                .with_context(|| format!("Invalid timestamp: {timestamp:?}!"))?,
+        ))
+    }
+}
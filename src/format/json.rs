@@ -0,0 +1,112 @@
+use std::mem;
+
+use anyhow::Result;
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::Message;
+
+/// The length of the braces, field names, and separators of a JSON encoded message.
+#[cfg(not(feature = "camel-case"))]
+const JSON_OVERHEAD: usize = r#"{"customer_id":,"consumption":,"timestamp":}"#.len();
+
+/// The length of the braces, field names, and separators of a JSON encoded message.
+#[cfg(feature = "camel-case")]
+const JSON_OVERHEAD: usize = r#"{"customerId":,"consumptionMwh":,"timestamp":}"#.len();
+
+/// The length of most JSON encoded consumptions, as the shortest representation of an `f32`
+/// rarely needs more digits.
+const CONSUMPTION_LEN_HINT: usize = 12;
+
+impl Message {
+    /// Encode the message as JSON.
+    ///
+    /// # Returns
+    ///
+    /// * The JSON object.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be serialized.
+    pub fn to_json(&self) -> Result<String> {
+        let mut json = String::with_capacity(self.size_hint());
+        self.write_json_into(&mut json)?;
+
+        Ok(json)
+    }
+
+    /// Encode the message as JSON into a buffer, replacing its contents, so that the buffer can
+    /// be reused between messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer to write the JSON object to.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be serialized, leaving the buffer empty.
+    pub fn write_json_into(&self, buf: &mut String) -> Result<()> {
+        let mut bytes = mem::take(buf).into_bytes();
+        bytes.clear();
+        serde_json::to_writer(&mut bytes, self)?;
+        *buf = String::from_utf8(bytes)?;
+
+        Ok(())
+    }
+
+    /// Estimate the length of the message when encoded as JSON, to pre-allocate buffers with.
+    ///
+    /// # Returns
+    ///
+    /// * The estimated number of bytes, exact unless the consumption has an unusually long
+    ///   representation.
+    #[must_use]
+    pub fn size_hint(&self) -> usize {
+        JSON_OVERHEAD
+            + digits(self.customer_id.into())
+            + CONSUMPTION_LEN_HINT
+            + digits(self.timestamp)
+    }
+
+    /// Decode a message from JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The JSON object.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes don't hold a valid message.
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Describe the JSON encoding of a message with a [JSON Schema](https://json-schema.org).
+    ///
+    /// # Returns
+    ///
+    /// * The schema document.
+    #[must_use]
+    pub fn json_schema() -> RootSchema {
+        schema_for!(Self)
+    }
+}
+
+/// Count the decimal digits of a number.
+///
+/// # Arguments
+///
+/// * `n` - The number to count the digits of.
+///
+/// # Returns
+///
+/// * The number of digits, at least one.
+const fn digits(n: u128) -> usize {
+    match n.checked_ilog10() {
+        Some(x) => x as usize + 1,
+        None => 1,
+    }
+}
@@ -0,0 +1,157 @@
+mod avro;
+mod bincode;
+mod csv;
+mod json;
+mod msgpack;
+mod protobuf;
+
+use anyhow::Result;
+use std::str;
+
+pub use avro::AVRO_SCHEMA;
+use clap::ValueEnum;
+pub use csv::CSV_HEADER;
+pub use protobuf::PROTO_SCHEMA;
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+/// The record header naming the format of the payload, see [`Format::content_type`].
+pub const CONTENT_TYPE_HEADER: &str = "content-type";
+
+/// The record header naming the version of the payload's format, see
+/// [`CURRENT_VERSION`](crate::envelope::CURRENT_VERSION).
+pub const SCHEMA_VERSION_HEADER: &str = "schema-version";
+
+/// The wire formats a `Message` can be encoded in.
+///
+/// # Variants
+///
+/// * `Json` - Human-readable JSON, see [`Message::to_json`].
+/// * `Avro` - Schema-based binary Avro, see [`Message::to_avro`].
+/// * `Bincode` - Compact binary bincode, see [`Message::to_bincode`].
+/// * `Csv` - A row of comma-separated values, see [`Message::to_csv_record`].
+/// * `Msgpack` - Compact binary MessagePack, see [`Message::to_msgpack`].
+/// * `Protobuf` - Schema-based binary Protobuf, see [`Message::to_protobuf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    #[default]
+    Json,
+    Avro,
+    Bincode,
+    Csv,
+    Msgpack,
+    Protobuf,
+}
+
+impl Format {
+    /// Encode a message in this format.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to encode.
+    ///
+    /// # Returns
+    ///
+    /// * The encoded bytes.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be encoded.
+    pub fn encode(self, message: &Message) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => message.to_json().map(String::into_bytes),
+            Self::Avro => message.to_avro(),
+            Self::Bincode => message.to_bincode(),
+            Self::Csv => Ok(message.to_csv_record().into_bytes()),
+            Self::Msgpack => message.to_msgpack(),
+            Self::Protobuf => message.to_protobuf(),
+        }
+    }
+
+    /// Check whether the format is binary rather than human-readable text.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the encoded bytes may hold arbitrary, non-UTF-8 data.
+    #[must_use]
+    pub const fn is_binary(self) -> bool {
+        match self {
+            Self::Json | Self::Csv => false,
+            Self::Avro | Self::Bincode | Self::Msgpack | Self::Protobuf => true,
+        }
+    }
+
+    /// Get the [media type](https://www.iana.org/assignments/media-types) of the format, as
+    /// named by the [`CONTENT_TYPE_HEADER`].
+    ///
+    /// # Returns
+    ///
+    /// * The media type of the encoded bytes.
+    #[must_use]
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Avro => "avro/binary",
+            Self::Bincode => "application/x-bincode",
+            Self::Csv => "text/csv",
+            Self::Msgpack => "application/msgpack",
+            Self::Protobuf => "application/x-protobuf",
+        }
+    }
+
+    /// Get the format named by a [`CONTENT_TYPE_HEADER`].
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - The value of the header.
+    ///
+    /// # Returns
+    ///
+    /// * The named format, `None` if it's unknown.
+    #[must_use]
+    pub fn from_content_type(content_type: &[u8]) -> Option<Self> {
+        Self::value_variants()
+            .iter()
+            .copied()
+            .find(|x| x.content_type().as_bytes() == content_type)
+    }
+
+    /// Get the header line to write once before a stream of encoded messages.
+    ///
+    /// # Returns
+    ///
+    /// * The header line, if the format has one.
+    #[must_use]
+    pub const fn header(self) -> Option<&'static str> {
+        match self {
+            Self::Csv => Some(CSV_HEADER),
+            Self::Json | Self::Avro | Self::Bincode | Self::Msgpack | Self::Protobuf => None,
+        }
+    }
+
+    /// Decode a message from this format.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The encoded bytes.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes don't hold a valid message.
+    pub fn decode(self, bytes: &[u8]) -> Result<Message> {
+        match self {
+            Self::Json => Message::from_json(bytes),
+            Self::Avro => Message::from_avro(bytes),
+            Self::Bincode => Message::from_bincode(bytes),
+            Self::Csv => Message::from_csv_record(str::from_utf8(bytes)?),
+            Self::Msgpack => Message::from_msgpack(bytes),
+            Self::Protobuf => Message::from_protobuf(bytes),
+        }
+    }
+}
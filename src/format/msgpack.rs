@@ -0,0 +1,37 @@
+use anyhow::Result;
+
+use crate::Message;
+
+impl Message {
+    /// Encode the message with [MessagePack](https://msgpack.org).
+    ///
+    /// The fields are written as an array rather than a map, leaving out their names.
+    ///
+    /// # Returns
+    ///
+    /// * The compact binary encoding.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be serialized.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec(self)?)
+    }
+
+    /// Decode a message encoded with [MessagePack](https://msgpack.org).
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The compact binary encoding.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes don't hold a valid message.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
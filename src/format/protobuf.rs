@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use prost::Message as _;
+
+use crate::{Message, MilliwattHours};
+
+/// The Protobuf schema of a `Message`, matching [`ProtoMessage`].
+pub const PROTO_SCHEMA: &str = r#"syntax = "proto3";
+
+package h4_bigdata;
+
+message Message {
+  // The ID of the customer.
+  uint32 customer_id = 1;
+  // The mWh of the customer's electrical consumption.
+  float consumption = 2;
+  // The time, in milliseconds since the Unix Epoch.
+  uint64 timestamp = 3;
+}
+"#;
+
+/// The Protobuf representation of a `Message`, as described by [`PROTO_SCHEMA`].
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoMessage {
+    #[prost(uint32, tag = "1")]
+    customer_id: u32,
+    #[prost(float, tag = "2")]
+    consumption: f32,
+    #[prost(uint64, tag = "3")]
+    timestamp: u64,
+}
+
+impl Message {
+    /// Encode the message with [Protobuf](https://protobuf.dev), as described by
+    /// [`PROTO_SCHEMA`].
+    ///
+    /// The timestamp is narrowed to a `uint64`, which holds any time until the year 584,556,019.
+    ///
+    /// # Returns
+    ///
+    /// * The Protobuf message.
+    ///
+    /// # Errors
+    ///
+    /// * If the timestamp doesn't fit in a Protobuf `uint64`.
+    pub fn to_protobuf(&self) -> Result<Vec<u8>> {
+        let message = ProtoMessage {
+            customer_id: self.customer_id,
+            consumption: self.consumption.0,
+            timestamp: u64::try_from(self.timestamp)
+                .context("Timestamp doesn't fit in a Protobuf uint64!")?,
+        };
+
+        Ok(message.encode_to_vec())
+    }
+
+    /// Decode a message from Protobuf, as described by [`PROTO_SCHEMA`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The Protobuf message.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes don't hold a valid message.
+    pub fn from_protobuf(bytes: &[u8]) -> Result<Self> {
+        let message = ProtoMessage::decode(bytes)?;
+
+        Ok(Self::new(
+            message.customer_id,
+            MilliwattHours(message.consumption),
+            message.timestamp.into(),
+        ))
+    }
+}
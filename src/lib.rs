@@ -1,11 +1,59 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+pub mod admin;
+pub mod aggregate;
+pub mod breaker;
+mod builder;
+pub mod clock;
+pub mod compression;
+pub mod config;
+pub mod enrich;
+pub mod envelope;
+pub mod error;
+pub mod format;
+pub mod manifest;
+pub mod meter;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod model;
+pub mod output;
+pub mod partition;
+pub mod producer;
+pub mod profile;
+pub mod ramp;
+pub mod recent;
+#[cfg(feature = "schema-registry")]
+pub mod registry;
+pub mod sanitize;
+pub mod shutdown;
+pub mod sink;
+pub mod source;
+pub mod stats;
+pub mod stream;
+pub mod timestamp;
+pub mod units;
 
-use rand::{prelude::ThreadRng, Rng};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter,
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+pub use builder::MessageBuilder;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use clock::Clock;
+use error::{MessageError, ParseMessageError};
+use model::ConsumptionModel;
+use rand::Rng;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+pub use units::MilliwattHours;
+
+/// The range of customer IDs generated by default, and allowed by the schema.
+pub const CUSTOMER_ID_RANGE: RangeInclusive<u32> = 1_000..=9_999;
 
-/// Wrapper type for `f32` when used as mWh.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub struct MilliwattHours(pub f32);
+/// The largest consumption in mWh generated by default.
+pub const DEFAULT_MAX_CONSUMPTION: f32 = 10.0;
 
 /// A message from or to a Kafka cluster.
 ///
@@ -14,9 +62,22 @@ pub struct MilliwattHours(pub f32);
 /// * `customer_id` - The ID of the customer.
 /// * `consumption` - The mWh of the customer's electrical consumption.
 /// * `timestamp` - The time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// The fields are serialized as `customer_id` and `consumption`, or as `customerId` and
+/// `consumptionMwh` with the `camel-case` feature. Either name is accepted when deserializing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Message {
+    #[cfg_attr(
+        feature = "camel-case",
+        serde(rename = "customerId", alias = "customer_id")
+    )]
+    #[cfg_attr(not(feature = "camel-case"), serde(alias = "customerId"))]
     customer_id: u32,
+    #[cfg_attr(
+        feature = "camel-case",
+        serde(rename = "consumptionMwh", alias = "consumption")
+    )]
+    #[cfg_attr(not(feature = "camel-case"), serde(alias = "consumptionMwh"))]
     consumption: MilliwattHours,
     timestamp: u128,
 }
@@ -42,28 +103,252 @@ impl Message {
         }
     }
 
+    /// Construct a new `Message` instance, rejecting physically impossible values.
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_id` - The ID of the customer.
+    /// * `consumption` - The mWh of the customer's electrical consumption.
+    /// * `timestamp` - The time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Message`.
+    ///
+    /// # Errors
+    ///
+    /// * If the `customer_id` is zero.
+    /// * If the `consumption` is NaN or infinite.
+    /// * If the `consumption` is negative.
+    pub fn try_new(
+        customer_id: u32,
+        consumption: MilliwattHours,
+        timestamp: u128,
+    ) -> Result<Self, MessageError> {
+        if customer_id == 0 {
+            return Err(MessageError::ZeroCustomerId);
+        }
+
+        if !consumption.0.is_finite() {
+            return Err(MessageError::NonFiniteConsumption(consumption.0));
+        }
+
+        if consumption.0 < 0.0 {
+            return Err(MessageError::NegativeConsumption(consumption.0));
+        }
+
+        Ok(Self::new(customer_id, consumption, timestamp))
+    }
+
+    /// Start building a new `Message` field by field.
+    ///
+    /// # Returns
+    ///
+    /// * A new `MessageBuilder` with no fields set.
+    #[must_use]
+    pub const fn builder() -> MessageBuilder {
+        MessageBuilder::new()
+    }
+
     /// Generate a new instance of `Message` with randomized values.
     ///
     /// # Arguments
     ///
     /// * `rng` - The randomness seed to use for generation.
+    /// * `clock` - The clock to timestamp the message with.
+    /// * `jitter_ms` - The most milliseconds the timestamp is randomly offset by, in either
+    ///   direction.
     ///
     /// # Returns
     ///
     /// * A new `Message` instance random values.
     ///
+    /// # Errors
+    ///
+    /// * If the `clock` couldn't tell the time.
+    pub fn with_rng<R: Rng + ?Sized>(
+        rng: &mut R,
+        clock: &dyn Clock,
+        jitter_ms: u64,
+    ) -> Result<Self, MessageError> {
+        let mut message =
+            Self::with_rng_in_range(rng, clock, CUSTOMER_ID_RANGE, DEFAULT_MAX_CONSUMPTION)?;
+        message.timestamp = jitter(rng, message.timestamp, jitter_ms);
+
+        Ok(message)
+    }
+
+    /// Generate a new instance of `Message` with randomized values, picking the customer ID from a
+    /// range.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness seed to use for generation.
+    /// * `clock` - The clock to timestamp the message with.
+    /// * `range` - The range of customer IDs to pick from.
+    /// * `max_consumption` - The mWh the consumption is picked below, e.g.
+    ///   [`DEFAULT_MAX_CONSUMPTION`].
+    ///
+    /// # Returns
+    ///
+    /// * A new `Message` instance with random values.
+    ///
+    /// # Errors
+    ///
+    /// * If the `clock` couldn't tell the time.
+    ///
     /// # Panics
     ///
-    /// * If the system time is less than the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
-    pub fn with_rng(rng: &mut ThreadRng) -> Self {
-        let customer_id = rng.random_range(1_000..=9_999);
-        let consumption = MilliwattHours(rng.random::<f32>() * 10.0);
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards!")
-            .as_millis();
+    /// * If the `range` is empty.
+    pub fn with_rng_in_range<R: Rng + ?Sized>(
+        rng: &mut R,
+        clock: &dyn Clock,
+        range: RangeInclusive<u32>,
+        max_consumption: f32,
+    ) -> Result<Self, MessageError> {
+        let customer_id = rng.random_range(range);
+        let consumption = MilliwattHours(rng.random::<f32>() * max_consumption);
+
+        Ok(Self::new(customer_id, consumption, clock.now_millis()?))
+    }
+
+    /// Generate a new instance of `Message` with randomized values, picking the timestamp from a
+    /// time window rather than using the current time.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness seed to use for generation.
+    /// * `start_ms` - The earliest timestamp to pick, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    /// * `end_ms` - The latest timestamp to pick, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    /// * `max_consumption` - The mWh the consumption is picked below, e.g.
+    ///   [`DEFAULT_MAX_CONSUMPTION`].
+    ///
+    /// # Returns
+    ///
+    /// * A new `Message` instance with random values.
+    ///
+    /// # Errors
+    ///
+    /// * If the window ends before it starts.
+    pub fn with_rng_in_window<R: Rng + ?Sized>(
+        rng: &mut R,
+        start_ms: u128,
+        end_ms: u128,
+        max_consumption: f32,
+    ) -> Result<Self, MessageError> {
+        if start_ms > end_ms {
+            return Err(MessageError::InvalidWindow {
+                start: start_ms,
+                end: end_ms,
+            });
+        }
+
+        let customer_id = rng.random_range(CUSTOMER_ID_RANGE);
+        let consumption = MilliwattHours(rng.random::<f32>() * max_consumption);
+        let timestamp = rng.random_range(start_ms..=end_ms);
 
-        Self::new(customer_id, consumption, timestamp)
+        Ok(Self::new(customer_id, consumption, timestamp))
+    }
+
+    /// Generate a batch of `Message`s with randomized values.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness seed to use for generation.
+    /// * `clock` - The clock to timestamp the messages with.
+    /// * `n` - The number of messages to generate.
+    ///
+    /// # Returns
+    ///
+    /// * The `n` generated messages.
+    ///
+    /// # Errors
+    ///
+    /// * If the `clock` couldn't tell the time.
+    pub fn batch_with_rng<R: Rng + ?Sized>(
+        rng: &mut R,
+        clock: &dyn Clock,
+        n: usize,
+    ) -> Result<Vec<Self>, MessageError> {
+        iter::repeat_with(|| Self::with_rng(rng, clock, 0))
+            .take(n)
+            .collect()
+    }
+
+    /// Generate a new instance of `Message` with its consumption sampled from a model.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness seed to use for generation.
+    /// * `clock` - The clock to timestamp the message with.
+    /// * `model` - The model to sample the consumption from.
+    ///
+    /// # Returns
+    ///
+    /// * A new `Message` instance with random values.
+    ///
+    /// # Errors
+    ///
+    /// * If the parameters of the `model` are invalid.
+    /// * If the `clock` couldn't tell the time.
+    pub fn with_model<R: Rng + ?Sized>(
+        rng: &mut R,
+        clock: &dyn Clock,
+        model: &ConsumptionModel,
+    ) -> Result<Self, MessageError> {
+        let customer_id = rng.random_range(CUSTOMER_ID_RANGE);
+        let consumption = model.sample(rng)?;
+
+        Ok(Self::new(customer_id, consumption, clock.now_millis()?))
+    }
+
+    /// Check that the customer ID is in the [`CUSTOMER_ID_RANGE`] allowed by the schema.
+    ///
+    /// # Errors
+    ///
+    /// * If the customer ID is outside of the [`CUSTOMER_ID_RANGE`].
+    pub const fn validate_customer_id(&self) -> Result<(), MessageError> {
+        if self.customer_id < *CUSTOMER_ID_RANGE.start()
+            || self.customer_id > *CUSTOMER_ID_RANGE.end()
+        {
+            return Err(MessageError::CustomerIdOutOfRange(self.customer_id));
+        }
+
+        Ok(())
+    }
+
+    /// Compare two messages, allowing their consumptions to differ by a small amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The message to compare with.
+    /// * `epsilon` - The largest difference in mWh for the consumptions to be considered equal.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the customer IDs and timestamps are equal, and the consumptions are within the
+    ///   `epsilon` of each other.
+    #[must_use]
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.customer_id == other.customer_id
+            && self.timestamp == other.timestamp
+            && (self.consumption.0 - other.consumption.0).abs() <= epsilon
+    }
+
+    /// Check that the timestamp of the message isn't too far in the future, as happens with a
+    /// misconfigured clock.
+    ///
+    /// # Arguments
+    ///
+    /// * `now_ms` - The current time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    /// * `max_skew_ms` - The number of milliseconds the timestamp may be ahead of `now_ms`.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the timestamp is at most `max_skew_ms` ahead of `now_ms`.
+    #[must_use]
+    pub const fn is_plausible(&self, now_ms: u128, max_skew_ms: u128) -> bool {
+        self.timestamp <= now_ms.saturating_add(max_skew_ms)
     }
 
     /// Get the customer ID of the message.
@@ -76,6 +361,16 @@ impl Message {
         self.customer_id
     }
 
+    /// Get the key the message is produced with, grouping the messages of a customer.
+    ///
+    /// # Returns
+    ///
+    /// * The customer ID in decimal, e.g. `"1042"`.
+    #[must_use]
+    pub fn key(&self) -> String {
+        self.customer_id.to_string()
+    }
+
     /// Get the mWh electrical consumption of the customer.
     ///
     /// # Returns
@@ -88,11 +383,135 @@ impl Message {
 
     /// Get the timestamp of the message.
     ///
+    /// Messages are timestamped in milliseconds, but may be sent in another unit, see
+    /// [`TimestampUnit`](crate::timestamp::TimestampUnit).
+    ///
     /// # Returns
     ///
-    /// * The timestamp, in milliseconds.
+    /// * The timestamp, in milliseconds unless converted to another unit.
     #[must_use]
     pub const fn timestamp(&self) -> u128 {
         self.timestamp
     }
+
+    /// Get the timestamp of the message in whole seconds.
+    ///
+    /// # Returns
+    ///
+    /// * The timestamp, in seconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time),
+    ///   rounded down.
+    #[must_use]
+    pub const fn timestamp_secs(&self) -> u128 {
+        self.timestamp / 1_000
+    }
+
+    /// Get the timestamp of the message as a UTC date and time.
+    ///
+    /// # Returns
+    ///
+    /// * The date and time, or `None` if the timestamp is out of the range supported by `chrono`.
+    #[cfg(feature = "chrono")]
+    #[must_use]
+    pub fn datetime_utc(&self) -> Option<DateTime<Utc>> {
+        i64::try_from(self.timestamp)
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+    }
+}
+
+/// Format the message on a single line, e.g.
+/// `customer=1042 consumption=6.5mWh ts=1700000000000`, parsed back by [`Message::from_str`].
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "customer={} consumption={}mWh ts={}",
+            self.customer_id, self.consumption.0, self.timestamp
+        )
+    }
+}
+
+/// Parse a message from the single line written by its [`Display`] implementation.
+///
+/// The fields are separated by whitespace and may come in any order.
+impl FromStr for Message {
+    type Err = ParseMessageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut customer_id = None;
+        let mut consumption = None;
+        let mut timestamp = None;
+        for field in s.split_whitespace() {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| ParseMessageError::MalformedField(field.to_string()))?;
+            match key {
+                "customer" => set_field(&mut customer_id, "customer", value, str::parse)?,
+                "consumption" => set_field(&mut consumption, "consumption", value, |x| {
+                    x.strip_suffix("mWh").ok_or(())?.parse().map_err(|_| ())
+                })?,
+                "ts" => set_field(&mut timestamp, "ts", value, str::parse)?,
+                _ => return Err(ParseMessageError::UnknownField(key.to_string())),
+            }
+        }
+
+        Ok(Self::new(
+            customer_id.ok_or(ParseMessageError::MissingField("customer"))?,
+            MilliwattHours(consumption.ok_or(ParseMessageError::MissingField("consumption"))?),
+            timestamp.ok_or(ParseMessageError::MissingField("ts"))?,
+        ))
+    }
+}
+
+/// Parse the value of a field of a message's string form, rejecting fields given twice.
+///
+/// # Arguments
+///
+/// * `slot` - The parsed value of the field, if already given.
+/// * `field` - The name of the field.
+/// * `value` - The unparsed value.
+/// * `parse` - The function to parse the value with.
+///
+/// # Errors
+///
+/// * If the field was already given.
+/// * If the value couldn't be parsed.
+fn set_field<T, E>(
+    slot: &mut Option<T>,
+    field: &'static str,
+    value: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<(), ParseMessageError> {
+    if slot.is_some() {
+        return Err(ParseMessageError::DuplicateField(field));
+    }
+
+    let parsed = parse(value).map_err(|_| ParseMessageError::InvalidValue {
+        field,
+        value: value.to_string(),
+    })?;
+    *slot = Some(parsed);
+
+    Ok(())
+}
+
+/// Offset a timestamp by a random number of milliseconds.
+///
+/// # Arguments
+///
+/// * `rng` - The randomness source to pick the offset with.
+/// * `timestamp` - The timestamp to offset, in milliseconds.
+/// * `jitter_ms` - The most milliseconds to offset by, in either direction.
+///
+/// # Returns
+///
+/// * The offset timestamp, clamped to the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+fn jitter<R: Rng + ?Sized>(rng: &mut R, timestamp: u128, jitter_ms: u64) -> u128 {
+    if jitter_ms == 0 {
+        return timestamp;
+    }
+
+    let offset = rng.random_range(-i128::from(jitter_ms)..=i128::from(jitter_ms));
+
+    timestamp.saturating_add_signed(offset)
 }
@@ -1,96 +1,1166 @@
-use anyhow::Result;
-use h4_bigdata::Message;
-use rdkafka::{
-    producer::{FutureProducer, FutureRecord},
-    ClientConfig,
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    iter,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
 };
-use tokio::task::JoinHandle;
-use tracing::{error, info, warn};
+
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "metrics")]
+use h4_bigdata::metrics;
+#[cfg(feature = "schema-registry")]
+use h4_bigdata::registry::RegistryClient;
+use h4_bigdata::{
+    admin,
+    breaker::{Admission, CircuitBreaker},
+    clock::{Clock, SystemClock},
+    config::{Command, Config, LogFormat, LogLevel},
+    enrich::StaticFields,
+    error::ProducerError,
+    manifest::Manifest,
+    model::ConsumptionModel,
+    output::DeadLetterFile,
+    partition,
+    producer::{self, ClusterMetadata, MessageProducer},
+    profile::ProfileRegistry,
+    ramp::RateRamp,
+    recent::RecentBuffer,
+    sanitize::Sanitize,
+    shutdown,
+    sink::{FileSink, MessageSink, StdoutSink},
+    source::{
+        CsvIngestSource, CustomerIdFilter, FileReplaySource, FutureSkewFilter, MessageSource,
+        MeterSource, Pacer, RandomSource,
+    },
+    stats::{self, RunSummary, Stats},
+    timestamp::TimestampUnit,
+    Message, MilliwattHours,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use rdkafka::producer::FutureProducer;
+use tokio::{signal, sync::Semaphore, task::JoinSet, time};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+/// The number of messages sampled for the key distribution, used when no count is given.
+const KEY_DISTRIBUTION_SAMPLE: u64 = 10_000;
+
+/// The number of characters in the bar of the fullest partition of the key distribution.
+const HISTOGRAM_WIDTH: u64 = 50;
+
+/// How long to wait for the brokers to report or create the topics when starting up.
+const TOPIC_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
-
-    let brokers: Vec<String> = [
-        "172.16.250.32:9092",
-        "172.16.250.33:9092",
-        "172.16.250.34:9092",
-        "172.16.250.35:9092",
-        "172.16.250.36:9092",
-        "172.16.250.37:9092",
-        "172.16.250.38:9092",
-        "172.16.250.39:9092",
-        "172.16.250.40:9092",
-        "172.16.250.41:9092",
-        "172.16.250.42:9092",
-    ]
-    .iter()
-    .map(|x| (*x).to_string())
-    .collect();
-
-    let topic = "household_consumption2";
-    let producer = create_producer(&brokers.join(","))?;
-
-    let mut rng = rand::rng();
-    let mut handles = Vec::new();
-    loop {
-        let message = Message::with_rng(&mut rng);
-        let json = serde_json::to_string(&message)?;
-
-        let result = producer
-            .send_result(
-                FutureRecord::to(topic)
-                    .key(&message.customer_id().to_string())
-                    .payload(json.as_bytes()),
-            )
-            .map_err(|(e, _)| e);
-        let result = match result {
-            Ok(v) => v,
-            Err(e) => {
-                error!("Kafka Error: {e}");
-                continue;
+    let mut config = Config::from_args()?;
+    init_logging(config.log_format, config.log_level);
+
+    if config.print_schema {
+        println!("{}", serde_json::to_string_pretty(&Message::json_schema())?);
+
+        return Ok(());
+    }
+
+    if let Some(partitions) = config.key_distribution {
+        return print_key_distribution(&config, partitions);
+    }
+
+    log_config(&config);
+
+    if config.validate_only {
+        return validate_input(&config);
+    }
+
+    if let Some(Command::Healthcheck { timeout }) = &config.command {
+        return healthcheck(&config, Duration::from_secs(*timeout)).await;
+    }
+
+    if let Some(Command::Tombstone { customer_ids }) = &config.command {
+        return send_tombstones(&config, customer_ids).await;
+    }
+
+    if let Some(path) = config.manifest.clone() {
+        write_manifest(&mut config, &path)?;
+    }
+
+    let producer = if config.dry_run {
+        info!("Dry run, printing messages instead of producing them.");
+        if let Some(header) = config.format.header() {
+            println!("{header}");
+        }
+
+        None
+    } else {
+        let (client, metadata) = connect(&config, config.topics(), TOPIC_TIMEOUT).await?;
+        let producer = MessageProducer::from_client(client, config.full_topic())
+            .with_format(config.format)
+            .with_compression(config.compress)
+            .with_max_bytes(config.max_bytes)
+            .with_timestamp_unit(config.timestamp_unit)
+            .with_keys(config.keying.is_keyed())
+            .with_topic_shards(config.topic_shards);
+        let created = ensure_topics(&config, &producer).await?;
+        // The metadata was fetched before the topics were created, so it doesn't count them.
+        let detected = if created { None } else { metadata.partitions };
+        let producer = resolve_partitions(&config, producer, detected);
+
+        let producer = if config.extra_fields.is_empty() {
+            producer
+        } else {
+            let fields = config
+                .extra_fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone().into()))
+                .collect();
+
+            producer.with_enricher(Arc::new(StaticFields(fields)))
+        };
+
+        #[cfg(feature = "schema-registry")]
+        let producer = match &config.schema_registry_url {
+            Some(url) => {
+                let schema_id = register_schema(url, &producer.topics()).await?;
+
+                producer.with_schema_id(Some(schema_id))
             }
+            None => producer,
         };
 
-        handles.push(tokio::spawn(async move {
-            match result.await {
-                Ok(Ok((_, id))) => info!("Produced Message: {id}"),
-                Ok(Err((e, _))) => error!("Kafka Error: {e}"),
-                Err(e) => warn!("Producer Cancelled: {e}"),
+        Some(producer)
+    };
+
+    // Kafka is written to separately, as its delivery reports are needed for the statistics.
+    let mut sinks: Vec<Box<dyn MessageSink>> = Vec::new();
+    if let Some(path) = &config.output_file {
+        info!("Writing messages to {}.", path.display());
+
+        let sink = FileSink::open(path, config.format)
+            .with_context(|| format!("Failed to open {}!", path.display()))?;
+        sinks.push(Box::new(sink));
+    }
+
+    if producer.is_none() {
+        sinks.push(Box::new(StdoutSink::new(config.format)));
+    }
+
+    let dead_letter = config
+        .dead_letter
+        .as_ref()
+        .map(|path| {
+            info!("Writing failed messages to {}.", path.display());
+
+            DeadLetterFile::open(path)
+                .map(Mutex::new)
+                .with_context(|| format!("Failed to open {}!", path.display()))
+        })
+        .transpose()?;
+
+    let shutdown = CancellationToken::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+
+        async move {
+            shutdown::wait_for_signal().await;
+
+            info!("Shutting down...");
+            shutdown.cancel();
+        }
+    });
+
+    let stats = Arc::new(Stats::default());
+    tokio::spawn(stats::track_peak_rate(Arc::clone(&stats)));
+    if config.stats_interval > 0 {
+        let period = Duration::from_secs(config.stats_interval);
+        let warmup = Duration::from_secs(config.warmup);
+
+        tokio::spawn(stats::report(Arc::clone(&stats), period, warmup));
+    }
+
+    if let (Some(producer), Some(poll_interval)) = (&producer, config.poll_interval) {
+        let producer = producer.clone();
+        let mut interval = time::interval(Duration::from_millis(poll_interval));
+
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                producer.poll(Duration::ZERO);
+            }
+        });
+    }
+
+    let recent = Arc::new(RecentBuffer::new(config.recent_buffer));
+    #[cfg(unix)]
+    if recent.is_enabled() {
+        tokio::spawn(dump_on_signal(Arc::clone(&recent)));
+    }
+
+    #[cfg(feature = "metrics")]
+    if let Some(addr) = config.metrics_addr {
+        let stats = Arc::clone(&stats);
+        let recent = Arc::clone(&recent);
+
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(stats, recent, addr).await {
+                error!("Metrics Error: {e:#}");
+            }
+        });
+    }
+
+    let batch_size = config.batch_size;
+    let ramp = config.ramp.or_else(|| config.rate.map(RateRamp::constant));
+    match (config.ramp, config.rate) {
+        (Some(ramp), _) => {
+            info!(
+                "Ramping from {} to {} messages per second over {:?} in batches of {batch_size}.",
+                ramp.start_rate, ramp.end_rate, ramp.duration,
+            );
+
+            tokio::spawn(log_ramp(ramp));
+        }
+        (None, Some(rate)) => {
+            info!("Producing {rate} messages per second in batches of {batch_size}.");
+        }
+        (None, None) => info!("Producing messages as fast as possible."),
+    }
+
+    let workers = config.workers;
+    if workers > 1 {
+        info!("Producing with {workers} workers.");
+    }
+
+    let pipeline = Arc::new(Pipeline {
+        producer,
+        sinks,
+        dead_letter,
+        recent,
+        stats: Arc::clone(&stats),
+        shutdown,
+        fatal: OnceLock::new(),
+        inflight: Arc::new(Semaphore::new(config.max_inflight as usize)),
+        max_retries: config.max_retries,
+        retry_base_delay: Duration::from_millis(config.retry_base_delay),
+        sanitize: config.sanitize,
+        timestamp_unit: config.timestamp_unit,
+        breaker: config.error_threshold.map(|threshold| {
+            CircuitBreaker::new(threshold, Duration::from_secs(config.breaker_cooldown))
+        }),
+    });
+
+    let started = Instant::now();
+    let mut tasks = JoinSet::new();
+    for worker in 0..workers {
+        let source = create_source(&config, worker)?;
+        let pacer = match config.time_scale {
+            Some(time_scale) if time_scale > 0.0 => Some(Pacer::new(time_scale)?),
+            Some(_) => None,
+            None => config.replay_realtime.then(Pacer::default),
+        };
+        let ramp = ramp.map(|ramp| ramp.scaled(1.0 / f64::from(workers)));
+        let count = config.count.map(|count| {
+            count / u64::from(workers) + u64::from(u64::from(worker) < count % u64::from(workers))
+        });
+
+        tasks.spawn(run_worker(
+            Arc::clone(&pipeline),
+            source,
+            pacer,
+            ramp,
+            batch_size,
+            count,
+        ));
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        result??;
+    }
+
+    for sink in &pipeline.sinks {
+        sink.flush()?;
+    }
+
+    let inflight = &pipeline.inflight;
+    let flushed = config.max_inflight as usize - inflight.available_permits();
+    info!("Waiting for {flushed} in-flight messages...");
+
+    let deadline = Instant::now() + Duration::from_secs(config.flush_timeout);
+    let _permits =
+        match time::timeout_at(deadline.into(), inflight.acquire_many(config.max_inflight)).await {
+            Ok(permits) => Some(permits?),
+            Err(_) => {
+                let pending = config.max_inflight as usize - inflight.available_permits();
+                warn!("Timed out waiting for {pending} in-flight messages!");
+
+                None
+            }
+        };
+    if let Some(producer) = &pipeline.producer {
+        match producer.flush(deadline.saturating_duration_since(Instant::now())) {
+            Ok(()) => info!("Flushed {flushed} messages."),
+            Err(e) => warn!(
+                error = %e,
+                "Timed out flushing, {} messages remain un-acked!",
+                producer.in_flight_count(),
+            ),
+        }
+    }
+
+    if let Some(dead_letter) = &pipeline.dead_letter {
+        dead_letter.lock().expect("Lock poisoned!").flush()?;
+    }
+
+    print_summary(&stats.summary(started.elapsed()), config.log_format);
+
+    if let Some(e) = pipeline.fatal.get() {
+        return Err(e.clone().into());
+    }
+
+    Ok(())
+}
+
+/// Everything shared between the workers producing messages.
+///
+/// # Fields
+///
+/// * `producer` - The producer to send the messages with, `None` on a dry run.
+/// * `sinks` - The other destinations of the messages, written to before they're produced.
+/// * `dead_letter` - The file to append messages that failed to be produced to, if any.
+/// * `recent` - The most recently produced messages.
+/// * `stats` - The counters of the produced messages.
+/// * `shutdown` - The token cancelled when shutting down, stopping new sends and retries.
+/// * `fatal` - The first fatal error of the producer, shutting down every worker.
+/// * `inflight` - The permits of the messages allowed to await delivery at once.
+/// * `max_retries` - The most times a message failing with a retryable error is retried.
+/// * `retry_base_delay` - The time to wait before the first retry of a message.
+/// * `sanitize` - How messages with non-finite consumptions are handled, sent as is if `None`.
+/// * `timestamp_unit` - The unit the timestamps are converted to before they're sent.
+/// * `breaker` - The circuit breaker pausing production on consecutive failures, if any.
+struct Pipeline {
+    producer: Option<MessageProducer>,
+    sinks: Vec<Box<dyn MessageSink>>,
+    dead_letter: Option<Mutex<DeadLetterFile>>,
+    recent: Arc<RecentBuffer>,
+    stats: Arc<Stats>,
+    shutdown: CancellationToken,
+    fatal: OnceLock<ProducerError>,
+    inflight: Arc<Semaphore>,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    sanitize: Option<Sanitize>,
+    timestamp_unit: TimestampUnit,
+    breaker: Option<CircuitBreaker>,
+}
+
+/// Produce messages from a source until it's exhausted, `count` messages were produced, or the
+/// pipeline is shut down.
+///
+/// Any wait is cut short by the shutdown, and the batch being gathered is dropped rather than
+/// sent.
+///
+/// # Arguments
+///
+/// * `pipeline` - The pipeline to produce the messages through.
+/// * `source` - The source of the messages, owned by this worker alone.
+/// * `pacer` - The pacer to preserve the time between the messages with, if any.
+/// * `ramp` - The number of messages to produce per second over time, as fast as possible if
+///   `None`.
+/// * `batch_size` - The number of messages to enqueue together before awaiting their delivery.
+/// * `count` - The number of messages to produce, unbounded if `None`.
+///
+/// # Errors
+///
+/// * If a sink failed to send a batch.
+/// * If the in-flight semaphore was closed.
+async fn run_worker(
+    pipeline: Arc<Pipeline>,
+    mut source: Box<dyn MessageSource + Send>,
+    mut pacer: Option<Pacer>,
+    ramp: Option<RateRamp>,
+    batch_size: usize,
+    count: Option<u64>,
+) -> Result<()> {
+    let start = time::Instant::now();
+    let mut next_batch = start;
+    let mut remaining = count;
+    while !pipeline.shutdown.is_cancelled() && remaining != Some(0) {
+        let mut probe = false;
+        if let Some(breaker) = &pipeline.breaker {
+            let Some((admission, paused)) = wait_for_breaker(breaker, &pipeline.shutdown).await
+            else {
+                break;
+            };
+
+            probe = admission == Admission::Probe;
+            if paused {
+                // Don't make up for the batches missed while paused.
+                next_batch = next_batch.max(time::Instant::now());
+            }
+        }
+
+        if let Some(ramp) = &ramp {
+            tokio::select! {
+                () = time::sleep_until(next_batch) => {}
+                () = pipeline.shutdown.cancelled() => break,
+            }
+
+            let rate = ramp.rate_at(start.elapsed());
+            next_batch += Duration::from_secs_f64(batch_size as f64 / rate);
+        }
+
+        let size = remaining.map_or(batch_size, |x| {
+            batch_size.min(usize::try_from(x).unwrap_or(usize::MAX))
+        });
+        let size = if probe { size.min(1) } else { size };
+        remaining = remaining.map(|x| x - size as u64);
+
+        let mut batch = Vec::with_capacity(size);
+        while batch.len() < size {
+            let Some(mut message) = source.next() else {
+                break;
             };
-        }));
 
-        drain_threadpool(&mut handles, 1024 * 1024).await;
+            if let Some(sanitize) = pipeline.sanitize {
+                let Ok(sanitized) = sanitize.apply(message) else {
+                    pipeline.stats.record_error();
+                    continue;
+                };
+
+                message = sanitized;
+            }
+
+            if let Some(pacer) = &mut pacer {
+                tokio::select! {
+                    () = pacer.wait(&message) => {}
+                    () = pipeline.shutdown.cancelled() => break,
+                }
+            }
+
+            batch.push(pipeline.timestamp_unit.apply(message));
+        }
+
+        if pipeline.shutdown.is_cancelled() {
+            break;
+        }
+
+        if batch.is_empty() {
+            info!("Message source exhausted.");
+            break;
+        }
+
+        for sink in &pipeline.sinks {
+            sink.send_batch(&batch).await?;
+        }
+
+        pipeline.recent.extend(&batch);
+
+        let Some(producer) = pipeline.producer.clone() else {
+            pipeline.stats.record_produced(batch.len() as u64);
+
+            continue;
+        };
+
+        let permits = u32::try_from(batch.len())?;
+        let permit = tokio::select! {
+            permit = Arc::clone(&pipeline.inflight).acquire_many_owned(permits) => permit?,
+            () = pipeline.shutdown.cancelled() => break,
+        };
+        let pipeline = Arc::clone(&pipeline);
+
+        tokio::spawn(async move {
+            let _permit = permit;
+
+            let start = Instant::now();
+            let results = producer.send_batch(&batch).await;
+            for (message, result) in batch.iter().zip(results) {
+                let customer_id = message.customer_id();
+                let result = producer::retry_failed(
+                    &producer,
+                    message,
+                    result,
+                    pipeline.max_retries,
+                    pipeline.retry_base_delay,
+                    &pipeline.shutdown,
+                )
+                .await;
+                if let Some(breaker) = &pipeline.breaker {
+                    match &result {
+                        Ok(_) => breaker.record_success(),
+                        Err(_) => breaker.record_failure(Instant::now()),
+                    }
+                }
+
+                match result {
+                    Ok(delivery) => {
+                        pipeline.stats.record_produced(1);
+                        pipeline.stats.record_latency(delivery.latency);
+                        debug!(
+                            customer_id,
+                            partition = delivery.partition,
+                            offset = delivery.offset,
+                            "Produced message.",
+                        );
+                    }
+                    Err(e) => {
+                        pipeline.stats.record_error();
+                        error!(customer_id, error = %e, "Failed to produce message.");
+
+                        if let Some(dead_letter) = &pipeline.dead_letter {
+                            let written = dead_letter
+                                .lock()
+                                .expect("Lock poisoned!")
+                                .write(message, &e.to_string());
+                            if let Err(e) = written {
+                                error!(customer_id, error = %e, "Failed to write dead letter.");
+                            }
+                        }
+
+                        if e.is_fatal() {
+                            let _ = pipeline.fatal.set(e);
+                            pipeline.shutdown.cancel();
+                        }
+                    }
+                }
+            }
+
+            if batch.len() > 1 {
+                let elapsed = start.elapsed();
+                debug!(
+                    "Produced {} messages in {elapsed:?} ({:.0} messages per second).",
+                    batch.len(),
+                    batch.len() as f64 / elapsed.as_secs_f64(),
+                );
+            }
+        });
     }
+
+    Ok(())
 }
 
-fn create_producer(bootstrap_server: &str) -> Result<FutureProducer> {
-    let config = ClientConfig::new()
-        .set("bootstrap.servers", bootstrap_server)
-        .set("queue.buffering.max.messages", "100000000")
-        .set("queue.buffering.max.ms", "0")
-        .set("batch.num.messages", "100")
-        .create()?;
+/// Wait until a circuit breaker lets messages through.
+///
+/// # Arguments
+///
+/// * `breaker` - The circuit breaker to wait for.
+/// * `shutdown` - The token cutting the wait short.
+///
+/// # Returns
+///
+/// * Whether messages may be sent as usual or only a single probe, along with whether production
+///   was paused, `None` if shut down while waiting.
+async fn wait_for_breaker(
+    breaker: &CircuitBreaker,
+    shutdown: &CancellationToken,
+) -> Option<(Admission, bool)> {
+    let mut paused = false;
+    loop {
+        match breaker.check(Instant::now()) {
+            Admission::Wait(delay) => {
+                paused = true;
+                tokio::select! {
+                    () = time::sleep(delay) => {}
+                    () = shutdown.cancelled() => return None,
+                }
+            }
+            admission => return Some((admission, paused)),
+        }
+    }
+}
+
+/// Install the global subscriber writing log lines to stderr, keeping stdout to the messages of a
+/// dry run.
+///
+/// # Arguments
+///
+/// * `format` - The format to write the log lines in.
+/// * `level` - The most verbose level of log lines to write, read from `RUST_LOG` if `None`, and
+///   `info` if that isn't set either.
+fn init_logging(format: LogFormat, level: Option<LogLevel>) {
+    let filter = match level {
+        Some(level) => EnvFilter::new(level.as_str()),
+        None => EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .from_env_lossy(),
+    };
 
-    Ok(config)
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr);
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
 }
 
-/// Drain the thread pool if the limit is exceeded.
+/// Write a manifest of the run, seeding the generator at random first if no seed was given, so
+/// that the run can be reproduced.
 ///
 /// # Arguments
 ///
-/// * `handles` - A mutable reference to the `JoinHandle` array.
-/// * `limit` - The maximum number of thread handles allowed to exist at once.
-async fn drain_threadpool(handles: &mut Vec<JoinHandle<()>>, limit: usize) {
-    if handles.len() < limit {
-        return;
+/// * `config` - The configuration of the run, updated with the seed.
+/// * `path` - The path of the manifest.
+///
+/// # Errors
+///
+/// * If the system time is before the Unix Epoch.
+/// * If the manifest couldn't be written.
+fn write_manifest(config: &mut Config, path: &Path) -> Result<()> {
+    let seed = *config.seed.get_or_insert_with(rand::random);
+    let manifest = Manifest::new(config, seed, SystemClock.now_millis()?)?;
+    manifest.write(path)?;
+
+    info!(
+        "Wrote the manifest of the run to {}, seeded with {seed}.",
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Log the effective configuration as a single structured line, so that the settings of a run can
+/// be told from its logs.
+///
+/// The SASL password is left out, as the logs are rarely kept as secret as it is.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to log.
+fn log_config(config: &Config) {
+    info!(
+        brokers = %config.brokers.join(","),
+        topic = %config.full_topic(),
+        format = ?config.format,
+        compress = ?config.compress,
+        keying = ?config.keying,
+        timestamp_unit = config.timestamp_unit.symbol(),
+        rate = ?config.rate,
+        ramp = ?config.ramp,
+        workers = config.workers,
+        seed = ?config.seed,
+        customer_range = ?config.customer_range,
+        customer_mode = ?config.customer_mode,
+        delivery_guarantee = ?config.delivery_guarantee,
+        acks = ?config.acks,
+        linger_ms = ?config.linger_ms,
+        security_protocol = ?config.security_protocol,
+        sasl_username = ?config.sasl_username,
+        "Starting with the effective configuration.",
+    );
+}
+
+/// Check that the brokers respond, logging the number of brokers and whether the topic exists.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the producer to check with.
+/// * `timeout` - The maximum time to wait for the brokers.
+///
+/// # Errors
+///
+/// * If the Kafka client couldn't be created.
+/// * If the brokers didn't respond before the `timeout`.
+async fn healthcheck(config: &Config, timeout: Duration) -> Result<()> {
+    let topic = config.full_topic();
+    let (_, metadata) = connect(config, vec![topic.clone()], timeout).await?;
+
+    info!("Brokers are reachable, found {} brokers.", metadata.brokers);
+    if metadata.partitions.is_some() {
+        info!("Topic {topic} exists.");
+    } else {
+        warn!("Topic {topic} doesn't exist!");
     }
 
-    info!("Draining thread pool...");
-    while let Some(thread) = handles.pop() {
-        if let Err(e) = thread.await {
-            error!("Failed to join thread: {e}");
+    Ok(())
+}
+
+/// Create the Kafka client of the producer, fetching the layout of the cluster it connected to.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the producer, deciding the brokers and client properties.
+/// * `topics` - The topics to count the partitions of.
+/// * `timeout` - The maximum time to wait for the brokers.
+///
+/// # Returns
+///
+/// * The Kafka client, along with the metadata of the cluster.
+///
+/// # Errors
+///
+/// * If the Kafka client couldn't be created.
+/// * If the brokers didn't respond before the `timeout`.
+async fn connect(
+    config: &Config,
+    topics: Vec<String>,
+    timeout: Duration,
+) -> Result<(FutureProducer, ClusterMetadata)> {
+    let brokers = config.brokers.join(",");
+    let properties = config.client_properties();
+    let (client, metadata) = tokio::task::spawn_blocking(move || {
+        producer::create_producer_with_metadata(&brokers, &properties, &topics, timeout)
+    })
+    .await?
+    .context("Brokers are unreachable!")?;
+    info!("Connected to {} brokers.", metadata.brokers);
+
+    Ok((client, metadata))
+}
+
+/// Register the schema of the messages for the values of every topic they're produced to.
+///
+/// # Arguments
+///
+/// * `url` - The base URL of the schema registry.
+/// * `topics` - The topics the messages are produced to, one per shard.
+///
+/// # Returns
+///
+/// * The ID of the schema, shared by every topic.
+///
+/// # Errors
+///
+/// * If the registry couldn't be reached or rejected the schema.
+/// * If the registry assigned the topics different IDs.
+#[cfg(feature = "schema-registry")]
+async fn register_schema(url: &str, topics: &[String]) -> Result<u32> {
+    let registry = RegistryClient::new(url);
+
+    let mut schema_id = None;
+    for topic in topics {
+        let id = registry.register(topic).await?;
+        if schema_id.is_some_and(|x| x != id) {
+            bail!("Schema registry assigned {topic} a different schema ID {id}!");
+        }
+
+        schema_id = Some(id);
+    }
+
+    schema_id.context("No topic to register the schema for!")
+}
+
+/// Send a tombstone for each customer, deleting them from a compacted topic.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the producer, deciding the topic and partition of each
+///   customer.
+/// * `customer_ids` - The IDs of the customers to delete.
+///
+/// # Errors
+///
+/// * If the Kafka client couldn't be created.
+/// * If the brokers didn't respond.
+/// * If any tombstone couldn't be delivered.
+async fn send_tombstones(config: &Config, customer_ids: &[u32]) -> Result<()> {
+    let (client, metadata) = connect(config, config.topics(), TOPIC_TIMEOUT).await?;
+    let producer = MessageProducer::from_client(client, config.full_topic())
+        .with_topic_shards(config.topic_shards);
+    let producer = resolve_partitions(config, producer, metadata.partitions);
+
+    let mut failed = 0;
+    for &customer_id in customer_ids {
+        match producer.send_tombstone(customer_id).await {
+            Ok(delivery) => info!(
+                customer_id,
+                partition = delivery.partition,
+                offset = delivery.offset,
+                "Sent tombstone.",
+            ),
+            Err(e) => {
+                error!(customer_id, "Failed to send tombstone: {e}");
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "Failed to send {failed} of {} tombstones!",
+            customer_ids.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Check that every topic the producer produces to exists, creating the missing ones if the
+/// configuration allows it.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the producer, deciding whether to create topics.
+/// * `producer` - The producer to check the topics of.
+///
+/// # Returns
+///
+/// * Whether any topic was created.
+///
+/// # Errors
+///
+/// * If the brokers didn't respond.
+/// * If a topic is missing and `create_topic` isn't set.
+/// * If a missing topic couldn't be created.
+async fn ensure_topics(config: &Config, producer: &MessageProducer) -> Result<bool> {
+    let checker = producer.clone();
+    let missing = tokio::task::spawn_blocking(move || checker.missing_topics(TOPIC_TIMEOUT))
+        .await?
+        .context("Brokers are unreachable!")?;
+    if missing.is_empty() {
+        return Ok(false);
+    }
+
+    let missing_list = missing.join(", ");
+    if !config.create_topic {
+        bail!(
+            "Topic {missing_list} doesn't exist! Create it manually, or pass --create-topic to create it."
+        );
+    }
+
+    info!("Creating topic {missing_list}...");
+    admin::create_topics(
+        &config.brokers.join(","),
+        &config.client_properties(),
+        &missing,
+        config.partitions,
+        config.replication_factor,
+        TOPIC_TIMEOUT,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Pin customers to the partitions of the topic when messages are keyed, using the count
+/// detected from the cluster unless the configuration sets the count explicitly.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the producer, possibly setting the partition count.
+/// * `producer` - The producer to pin the customers of.
+/// * `detected` - The partitions counted from the cluster, see [`ClusterMetadata::partitions`].
+///
+/// # Returns
+///
+/// * The producer, pinning customers to the resolved number of partitions.
+fn resolve_partitions(
+    config: &Config,
+    producer: MessageProducer,
+    detected: Option<usize>,
+) -> MessageProducer {
+    if !config.keying.is_keyed() {
+        return producer;
+    }
+
+    let detected = detected.and_then(|x| i32::try_from(x).ok());
+    let partitions = match (config.partitions, detected) {
+        (Some(explicit), Some(actual)) => {
+            if explicit != actual {
+                warn!(
+                    "--partitions {explicit} doesn't match the {actual} partitions of the topic, customers may be misrouted!",
+                );
+            }
+
+            Some(explicit)
+        }
+        (None, Some(actual)) => {
+            info!("Detected {actual} partitions, pinning customers to them.");
+
+            Some(actual)
+        }
+        (explicit, None) => explicit,
+    };
+
+    producer.with_partitions(partitions)
+}
+
+/// Check that every record of the replayed or ingested file parses, logging the line of every
+/// record that doesn't, along with the number of valid and invalid records.
+///
+/// # Arguments
+///
+/// * `config` - The configuration naming the file to check.
+///
+/// # Errors
+///
+/// * If no file to replay or ingest is configured.
+/// * If the file couldn't be read.
+/// * If any record is invalid.
+fn validate_input(config: &Config) -> Result<()> {
+    let (path, parse): (_, fn(&str) -> Result<Message>) = match (&config.replay, &config.ingest_csv)
+    {
+        (Some(path), _) => (path, |x| Message::from_json(x.as_bytes())),
+        (None, Some(path)) => (path, Message::from_csv_record),
+        (None, None) => bail!("Validating requires a file to replay or ingest!"),
+    };
+    info!("Validating the records of {}.", path.display());
+
+    let file = File::open(path).with_context(|| format!("Failed to open {}!", path.display()))?;
+    let (mut valid, mut invalid) = (0_u64, 0_u64);
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line_number = i + 1;
+        let line =
+            line.with_context(|| format!("Failed to read line {line_number} of the file!"))?;
+        let line = line.trim();
+
+        let is_header =
+            line_number == 1 && config.ingest_csv.is_some() && line.starts_with("customer_id");
+        if line.is_empty() || is_header {
             continue;
         }
+
+        match parse(line) {
+            Ok(_) => valid += 1,
+            Err(e) => {
+                invalid += 1;
+                warn!("Invalid record on line {line_number}: {e:#}");
+            }
+        }
+    }
+
+    info!("Found {valid} valid and {invalid} invalid records.");
+    if invalid > 0 {
+        bail!("{} has {invalid} invalid records!", path.display());
+    }
+
+    Ok(())
+}
+
+/// Print a histogram of how the messages would be spread across partitions, and how skewed they
+/// are.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to generate messages with, sampling `count` of them.
+/// * `partitions` - The number of partitions to spread the messages across.
+///
+/// # Errors
+///
+/// * If the message source couldn't be created.
+fn print_key_distribution(config: &Config, partitions: i32) -> Result<()> {
+    let sample = config.count.unwrap_or(KEY_DISTRIBUTION_SAMPLE);
+    let mut source = create_source(config, 0)?;
+    let customer_ids = iter::from_fn(|| source.next())
+        .take(usize::try_from(sample).unwrap_or(usize::MAX))
+        .map(|x| x.customer_id());
+
+    let counts = partition::distribution(customer_ids, partitions);
+    let max = counts.iter().copied().max().unwrap_or(0);
+    for (partition, count) in counts.iter().enumerate() {
+        let width = (count * HISTOGRAM_WIDTH).checked_div(max).unwrap_or(0);
+        let bar = "#".repeat(usize::try_from(width).unwrap_or(0));
+
+        println!("{partition:>4} {count:>10} {bar}");
+    }
+
+    match partition::skew(&counts) {
+        Some(skew) => println!("Skew (max/avg): {skew:.3}"),
+        None => println!("No messages were generated."),
+    }
+
+    Ok(())
+}
+
+/// Create the source of the messages a worker produces.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to create the source from.
+/// * `worker` - The index of the worker, added to the seed so that every worker generates
+///   different messages.
+///
+/// # Returns
+///
+/// * The replayed or ingested file if one is configured, randomly generated messages otherwise.
+///
+/// # Errors
+///
+/// * If the replayed or ingested file couldn't be opened.
+/// * If the consumption model is invalid.
+fn create_source(config: &Config, worker: u32) -> Result<Box<dyn MessageSource + Send>> {
+    if let Some(path) = &config.replay {
+        info!("Replaying messages from {}.", path.display());
+
+        let source = FileReplaySource::open(path)
+            .with_context(|| format!("Failed to open {}!", path.display()))?;
+
+        return Ok(with_filters(config, source));
+    }
+
+    if let Some(path) = &config.ingest_csv {
+        info!("Ingesting messages from {}.", path.display());
+
+        let source = CsvIngestSource::open(path)
+            .with_context(|| format!("Failed to open {}!", path.display()))?;
+
+        return Ok(with_filters(config, source));
+    }
+
+    let rng = config.seed.map_or_else(StdRng::from_os_rng, |seed| {
+        let seed = seed.wrapping_add(u64::from(worker));
+        info!("Seeding the generator with {seed}.");
+
+        StdRng::seed_from_u64(seed)
+    });
+
+    if let Some(meters) = config.meters {
+        info!("Simulating {meters} meters.");
+
+        let mean = MilliwattHours(config.max_consumption / 2.0);
+        let source = MeterSource::new(
+            rng,
+            meters,
+            &config.customer_range,
+            mean,
+            Arc::new(SystemClock),
+        )?;
+
+        return Ok(with_filters(config, source));
+    }
+
+    let model = config.model.unwrap_or(ConsumptionModel::Uniform {
+        min: 0.0,
+        max: config.max_consumption,
+    });
+    let mut source = RandomSource::new(rng, model, config.customer_range.clone())?
+        .with_timestamp_jitter(config.timestamp_jitter)
+        .with_customer_skew(config.skew)?
+        .with_customer_mode(config.customer_mode);
+    if let Some(path) = &config.profiles {
+        let profiles = ProfileRegistry::load(path)?;
+        info!("Loaded the profiles of {} customers.", profiles.len());
+
+        source = source.with_profiles(Arc::new(profiles));
+    }
+
+    Ok(with_filters(config, source))
+}
+
+/// Wrap a source in the configured filters.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the filters.
+/// * `source` - The source to filter.
+///
+/// # Returns
+///
+/// * The filtered source.
+fn with_filters(
+    config: &Config,
+    source: impl MessageSource + Send + 'static,
+) -> Box<dyn MessageSource + Send> {
+    let mut source: Box<dyn MessageSource + Send> = Box::new(source);
+    if let Some(max_skew_ms) = config.reject_future_skew {
+        source = Box::new(FutureSkewFilter::new(source, max_skew_ms));
+    }
+
+    if config.strict_customer_id {
+        source = Box::new(CustomerIdFilter::new(source));
+    }
+
+    source
+}
+
+/// Print the report of a run to stderr, as a table or, when logging JSON, as a single structured
+/// event.
+///
+/// # Arguments
+///
+/// * `summary` - The report to print.
+/// * `format` - The format log lines are written in.
+fn print_summary(summary: &RunSummary, format: LogFormat) {
+    let latency = summary.latency;
+    if format == LogFormat::Json {
+        info!(
+            total = summary.total(),
+            produced = summary.produced,
+            errors = summary.errors,
+            elapsed_secs = summary.elapsed.as_secs_f64(),
+            average_rate = summary.average_rate(),
+            peak_rate = summary.peak_rate,
+            latency_p50_ms = latency.map(|x| x.p50.as_secs_f64() * 1_000.0),
+            latency_p95_ms = latency.map(|x| x.p95.as_secs_f64() * 1_000.0),
+            latency_p99_ms = latency.map(|x| x.p99.as_secs_f64() * 1_000.0),
+            latency_max_ms = latency.map(|x| x.max.as_secs_f64() * 1_000.0),
+            "Run summary.",
+        );
+
+        return;
+    }
+
+    let mut rows = vec![
+        ("Messages", summary.total().to_string()),
+        ("Produced", summary.produced.to_string()),
+        ("Errors", summary.errors.to_string()),
+        ("Elapsed", format!("{:.3?}", summary.elapsed)),
+        (
+            "Average rate",
+            format!("{:.0} msg/s", summary.average_rate()),
+        ),
+        ("Peak rate", format!("{} msg/s", summary.peak_rate)),
+    ];
+    if let Some(latency) = latency {
+        rows.extend([
+            ("Latency p50", format!("{:?}", latency.p50)),
+            ("Latency p95", format!("{:?}", latency.p95)),
+            ("Latency p99", format!("{:?}", latency.p99)),
+            ("Latency max", format!("{:?}", latency.max)),
+        ]);
+    }
+
+    // Keeping stdout to the messages of a dry run.
+    eprintln!("Run summary:");
+    for (name, value) in rows {
+        eprintln!("  {name:<14}{value:>16}");
+    }
+}
+
+/// Log the target rate of a ramp every second, until the ramp is over.
+///
+/// # Arguments
+///
+/// * `ramp` - The ramp to log the target rate of.
+async fn log_ramp(ramp: RateRamp) {
+    let start = Instant::now();
+    let mut interval = time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+
+        let elapsed = start.elapsed();
+        if elapsed >= ramp.duration {
+            info!("Holding at {} messages per second.", ramp.end_rate);
+            break;
+        }
+
+        info!(
+            "Targeting {:.0} messages per second.",
+            ramp.rate_at(elapsed)
+        );
+    }
+}
+
+/// Log the most recently produced messages as JSON every time the process receives SIGUSR1,
+/// forever.
+///
+/// # Arguments
+///
+/// * `recent` - The most recently produced messages.
+#[cfg(unix)]
+async fn dump_on_signal(recent: Arc<RecentBuffer>) {
+    let mut stream = match signal::unix::signal(signal::unix::SignalKind::user_defined1()) {
+        Ok(stream) => stream,
+        Err(e) => {
+            error!("Failed to listen for SIGUSR1: {e}");
+            return;
+        }
+    };
+
+    while stream.recv().await.is_some() {
+        match recent.to_json() {
+            Ok(messages) => info!(%messages, "Dumping the recently produced messages."),
+            Err(e) => error!(error = %e, "Failed to encode the recently produced messages."),
+        }
     }
 }
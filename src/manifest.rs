@@ -0,0 +1,110 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// A record of a run, enough to generate the same messages again with
+/// [`Manifest::into_config`].
+///
+/// The customers and consumptions are reproduced exactly, while the timestamps are still read
+/// from the clock of the new run.
+///
+/// # Fields
+///
+/// * `version` - The version of the producer.
+/// * `git_hash` - The commit the producer was built from, if known.
+/// * `started_at` - When the run started, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+/// * `seed` - The seed of the generator.
+/// * `config` - The configuration of the run, keyed like the configuration file, see
+///   [`Config::to_json`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    pub git_hash: Option<String>,
+    pub started_at: u128,
+    pub seed: u64,
+    pub config: serde_json::Value,
+}
+
+impl Manifest {
+    /// Construct a new `Manifest` of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the run.
+    /// * `seed` - The seed of the generator.
+    /// * `started_at` - When the run started, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time).
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Manifest`.
+    ///
+    /// # Errors
+    ///
+    /// * If the configuration can't be represented in JSON.
+    pub fn new(config: &Config, seed: u64, started_at: u128) -> Result<Self> {
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: option_env!("GIT_HASH").map(str::to_string),
+            started_at,
+            seed,
+            config: config.to_json()?,
+        })
+    }
+
+    /// Read a manifest from a JSON file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// * The read `Manifest`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be read, or isn't a valid manifest.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}!", path.display()))?;
+
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid manifest in {}!", path.display()))
+    }
+
+    /// Write the manifest to a JSON file, replacing it if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be written.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+
+        fs::write(path, contents).with_context(|| format!("Failed to write {}!", path.display()))
+    }
+
+    /// Resolve the configuration of the run, seeded like it was.
+    ///
+    /// # Returns
+    ///
+    /// * The configuration of the run.
+    ///
+    /// # Errors
+    ///
+    /// * If the configuration holds unknown keys or invalid values.
+    pub fn into_config(self) -> Result<Config> {
+        let mut config = Config::from_json(self.config).context("Invalid manifest config!")?;
+        config.seed = Some(self.seed);
+
+        Ok(config)
+    }
+}
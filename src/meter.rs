@@ -0,0 +1,127 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use rand::Rng;
+use rand_distr::{Distribution, StandardNormal};
+
+use crate::MilliwattHours;
+
+/// The number of hours in a day.
+const HOURS_PER_DAY: f32 = 24.0;
+
+/// The hour of the day at which consumption peaks.
+const PEAK_HOUR: f32 = 18.0;
+
+/// How far the daily profile swings around its mean, as a fraction of it.
+const DAILY_AMPLITUDE: f32 = 0.5;
+
+/// How quickly readings are pulled back towards the daily profile, per hour.
+const REVERSION_RATE: f32 = 2.0;
+
+/// The standard deviation of the random walk over an hour, as a fraction of the mean.
+const VOLATILITY: f32 = 0.25;
+
+/// A simulated electricity meter, whose consecutive readings are correlated and follow a daily
+/// profile peaking in the evening.
+///
+/// The readings are a mean-reverting random walk around the profile, so that a meter read twice
+/// in quick succession reports similar consumptions.
+///
+/// # Fields
+///
+/// * `customer_id` - The ID of the customer the meter belongs to.
+/// * `mean` - The consumption the daily profile swings around.
+/// * `hour` - The current hour of the day, in `0.0..24.0`.
+/// * `reading` - The previous reading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Meter {
+    customer_id: u32,
+    mean: MilliwattHours,
+    hour: f32,
+    reading: MilliwattHours,
+}
+
+impl Meter {
+    /// Construct a new `Meter` instance, starting on its daily profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_id` - The ID of the customer the meter belongs to.
+    /// * `mean` - The consumption the daily profile swings around.
+    /// * `hour` - The hour of the day the meter starts at.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Meter`.
+    #[must_use]
+    pub fn new(customer_id: u32, mean: MilliwattHours, hour: f32) -> Self {
+        let hour = hour.rem_euclid(HOURS_PER_DAY);
+
+        Self {
+            customer_id,
+            mean,
+            hour,
+            reading: profile(mean, hour),
+        }
+    }
+
+    /// Advance the meter and take its next reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness source to generate with.
+    /// * `dt` - The time passed since the previous reading.
+    ///
+    /// # Returns
+    ///
+    /// * The next consumption, never below zero.
+    pub fn next_reading<R: Rng + ?Sized>(&mut self, rng: &mut R, dt: Duration) -> MilliwattHours {
+        let hours = dt.as_secs_f32() / 3_600.0;
+        self.hour = (self.hour + hours).rem_euclid(HOURS_PER_DAY);
+
+        let target = profile(self.mean, self.hour);
+        let noise: f32 = StandardNormal.sample(rng);
+        let reading = self.reading.0
+            + REVERSION_RATE * (target.0 - self.reading.0) * hours.min(1.0 / REVERSION_RATE)
+            + VOLATILITY * self.mean.0 * hours.sqrt() * noise;
+
+        self.reading = MilliwattHours(reading.max(0.0));
+
+        self.reading
+    }
+
+    /// Get the customer ID of the meter.
+    ///
+    /// # Returns
+    ///
+    /// * The customer's ID as a `u32`.
+    #[must_use]
+    pub const fn customer_id(&self) -> u32 {
+        self.customer_id
+    }
+
+    /// Get the previous reading of the meter.
+    ///
+    /// # Returns
+    ///
+    /// * The previous consumption, in mWh.
+    #[must_use]
+    pub const fn reading(&self) -> MilliwattHours {
+        self.reading
+    }
+}
+
+/// Get the expected consumption at an hour of the day.
+///
+/// # Arguments
+///
+/// * `mean` - The consumption the profile swings around.
+/// * `hour` - The hour of the day.
+///
+/// # Returns
+///
+/// * The expected consumption, peaking at the [`PEAK_HOUR`].
+fn profile(mean: MilliwattHours, hour: f32) -> MilliwattHours {
+    let phase = (hour - PEAK_HOUR) / HOURS_PER_DAY * TAU;
+
+    mean * (1.0 + DAILY_AMPLITUDE * phase.cos())
+}
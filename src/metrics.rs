@@ -0,0 +1,136 @@
+use std::{fmt::Write as _, net::SocketAddr, sync::Arc};
+
+use anyhow::Result;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{info, warn};
+
+use crate::{
+    recent::RecentBuffer,
+    stats::{Stats, LATENCY_BUCKETS},
+};
+
+/// Serve the counters as [Prometheus](https://prometheus.io) metrics on `/metrics`, and the most
+/// recently produced messages as JSON on `/debug/recent`, forever.
+///
+/// # Arguments
+///
+/// * `stats` - The counters to expose.
+/// * `recent` - The most recently produced messages to expose.
+/// * `addr` - The address to listen on.
+///
+/// # Errors
+///
+/// * If the address couldn't be bound or no more connections can be accepted.
+pub async fn serve(stats: Arc<Stats>, recent: Arc<RecentBuffer>, addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving metrics on http://{addr}/metrics");
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let stats = Arc::clone(&stats);
+        let recent = Arc::clone(&recent);
+
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &stats, &recent).await {
+                warn!("Failed to serve metrics to {peer}: {e}");
+            }
+        });
+    }
+}
+
+/// Answer a single HTTP request, closing the connection afterwards.
+///
+/// # Arguments
+///
+/// * `stream` - The connection to answer.
+/// * `stats` - The counters to expose.
+/// * `recent` - The most recently produced messages to expose.
+///
+/// # Errors
+///
+/// * If the connection failed.
+/// * If the recent messages couldn't be encoded.
+async fn respond(mut stream: TcpStream, stats: &Stats, recent: &RecentBuffer) -> Result<()> {
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    // Skip the headers, nothing in them changes the response.
+    let mut header = String::new();
+    while reader.read_line(&mut header).await? > 2 {
+        header.clear();
+    }
+
+    let response = if request_line.starts_with("GET /metrics ") {
+        let body = render(stats);
+
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len(),
+        )
+    } else if request_line.starts_with("GET /debug/recent ") {
+        let body = recent.to_json()?;
+
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len(),
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+
+    writer.write_all(response.as_bytes()).await?;
+
+    Ok(())
+}
+
+/// Render the counters in the Prometheus text exposition format.
+///
+/// # Arguments
+///
+/// * `stats` - The counters to render.
+///
+/// # Returns
+///
+/// * The rendered metrics.
+fn render(stats: &Stats) -> String {
+    let latency = stats.latency();
+
+    let mut body = format!(
+        "# HELP h4_bigdata_messages_produced_total The number of messages produced successfully.
+# TYPE h4_bigdata_messages_produced_total counter
+h4_bigdata_messages_produced_total {}
+# HELP h4_bigdata_send_errors_total The number of messages that failed to be produced.
+# TYPE h4_bigdata_send_errors_total counter
+h4_bigdata_send_errors_total {}
+# HELP h4_bigdata_send_latency_seconds The time from enqueueing a message until it was acknowledged.
+# TYPE h4_bigdata_send_latency_seconds histogram
+",
+        stats.produced(),
+        stats.errors(),
+    );
+
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(latency.buckets) {
+        let _ = writeln!(
+            body,
+            "h4_bigdata_send_latency_seconds_bucket{{le=\"{bound}\"}} {count}"
+        );
+    }
+
+    let _ = write!(
+        body,
+        "h4_bigdata_send_latency_seconds_bucket{{le=\"+Inf\"}} {count}
+h4_bigdata_send_latency_seconds_sum {}
+h4_bigdata_send_latency_seconds_count {count}
+",
+        latency.sum.as_secs_f64(),
+        count = latency.count,
+    );
+
+    body
+}
@@ -0,0 +1,97 @@
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Normal, Uniform};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ModelError, MilliwattHours, DEFAULT_MAX_CONSUMPTION};
+
+/// A model of how the consumption of a household is distributed.
+///
+/// # Variants
+///
+/// * `Uniform` - Every consumption in `min..max` mWh is equally likely.
+/// * `Normal` - The consumption clusters around `mean` mWh, negative samples are clamped to zero.
+/// * `Exponential` - Small consumptions are the most likely, falling off at a rate of `lambda`.
+///
+/// Deserialized from a table naming the variant in its `kind`, e.g. `{ kind = "normal", mean = 5.0,
+/// std = 1.5 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ConsumptionModel {
+    Uniform { min: f32, max: f32 },
+    Normal { mean: f32, std: f32 },
+    Exponential { lambda: f32 },
+}
+
+impl Default for ConsumptionModel {
+    fn default() -> Self {
+        Self::Uniform {
+            min: 0.0,
+            max: DEFAULT_MAX_CONSUMPTION,
+        }
+    }
+}
+
+impl ConsumptionModel {
+    /// Check that the parameters of the model are valid.
+    ///
+    /// # Errors
+    ///
+    /// * If the uniform range is empty or not finite.
+    /// * If the standard deviation is negative or not finite.
+    /// * If the rate isn't a positive, finite number.
+    pub fn validate(&self) -> Result<(), ModelError> {
+        match *self {
+            Self::Uniform { min, max } => {
+                if !(min.is_finite() && max.is_finite() && min < max) {
+                    return Err(ModelError::EmptyRange { min, max });
+                }
+            }
+            Self::Normal { mean, std } => {
+                if !(mean.is_finite() && std.is_finite() && std >= 0.0) {
+                    return Err(ModelError::InvalidStandardDeviation(std));
+                }
+            }
+            Self::Exponential { lambda } => {
+                if !(lambda.is_finite() && lambda > 0.0) {
+                    return Err(ModelError::InvalidRate(lambda));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sample a consumption from the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness source to sample with.
+    ///
+    /// # Returns
+    ///
+    /// * The sampled consumption, in mWh.
+    ///
+    /// # Errors
+    ///
+    /// * If the parameters of the model are invalid.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<MilliwattHours, ModelError> {
+        let consumption = match *self {
+            Self::Uniform { min, max } => Uniform::new(min, max)
+                .map_err(|_| ModelError::EmptyRange { min, max })?
+                .sample(rng),
+            Self::Normal { mean, std } => Normal::new(mean, std)
+                .map_err(|_| ModelError::InvalidStandardDeviation(std))?
+                .sample(rng)
+                .max(0.0),
+            Self::Exponential { lambda } => {
+                self.validate()?;
+
+                Exp::new(lambda)
+                    .map_err(|_| ModelError::InvalidRate(lambda))?
+                    .sample(rng)
+            }
+        };
+
+        Ok(MilliwattHours(consumption))
+    }
+}
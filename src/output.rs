@@ -0,0 +1,215 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{format::Format, Message};
+
+/// How often buffered messages are flushed to the output file.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A file the produced messages are appended to, one per line.
+///
+/// # Fields
+///
+/// * `writer` - The buffered writer of the file.
+/// * `format` - The format to encode messages in.
+/// * `last_flush` - When the buffer was last flushed.
+#[derive(Debug)]
+pub struct OutputFile {
+    writer: BufWriter<File>,
+    format: Format,
+    last_flush: Instant,
+}
+
+impl OutputFile {
+    /// Open a file to append messages to, creating it if it doesn't exist.
+    ///
+    /// The header line of the format is written if the file is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `format` - The format to encode messages in.
+    ///
+    /// # Returns
+    ///
+    /// * The opened `OutputFile`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be opened or written to.
+    pub fn open(path: impl AsRef<Path>, format: Format) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let is_empty = file.metadata()?.len() == 0;
+
+        let mut writer = BufWriter::new(file);
+        if let Some(header) = format.header().filter(|_| is_empty) {
+            writeln!(writer, "{header}")?;
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Append a batch of messages, flushing them if the buffer wasn't flushed recently.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The messages to append.
+    ///
+    /// # Errors
+    ///
+    /// * If a message couldn't be encoded.
+    /// * If the file couldn't be written to.
+    pub fn write_batch(&mut self, batch: &[Message]) -> Result<()> {
+        write_batch(&mut self.writer, batch, self.format)?;
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush the buffered messages to the file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be written to.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.last_flush = Instant::now();
+
+        Ok(())
+    }
+}
+
+/// A line of the dead-letter file.
+///
+/// # Fields
+///
+/// * `message` - The message that couldn't be produced.
+/// * `error` - Why the message couldn't be produced.
+#[derive(Serialize)]
+struct DeadLetter<'a> {
+    message: &'a Message,
+    error: &'a str,
+}
+
+/// A [JSON Lines](https://jsonlines.org) file the messages that couldn't be produced are appended
+/// to, along with the reason, so that they may be reprocessed later.
+///
+/// # Fields
+///
+/// * `writer` - The buffered writer of the file.
+/// * `last_flush` - When the buffer was last flushed.
+#[derive(Debug)]
+pub struct DeadLetterFile {
+    writer: BufWriter<File>,
+    last_flush: Instant,
+}
+
+impl DeadLetterFile {
+    /// Open a file to append dead letters to, creating it if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// * The opened `DeadLetterFile`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Append a message that couldn't be produced, flushing it if the buffer wasn't flushed
+    /// recently.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message that couldn't be produced.
+    /// * `error` - Why the message couldn't be produced.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be written to.
+    pub fn write(&mut self, message: &Message, error: &str) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, &DeadLetter { message, error })?;
+        writeln!(self.writer)?;
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush the buffered dead letters to the file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be written to.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.last_flush = Instant::now();
+
+        Ok(())
+    }
+}
+
+/// Write a batch of messages, one per line.
+///
+/// Binary formats are written hex-encoded so that every message stays on a single line.
+///
+/// # Arguments
+///
+/// * `writer` - The writer to write to.
+/// * `batch` - The messages to write.
+/// * `format` - The format to encode the messages in.
+///
+/// # Errors
+///
+/// * If a message couldn't be encoded.
+/// * If the writer couldn't be written to.
+pub fn write_batch(writer: &mut impl Write, batch: &[Message], format: Format) -> Result<()> {
+    if format == Format::Json {
+        // Reusing a single buffer rather than allocating one per message.
+        let mut json = String::new();
+        for message in batch {
+            message.write_json_into(&mut json)?;
+            writeln!(writer, "{json}")?;
+        }
+
+        return Ok(());
+    }
+
+    for message in batch {
+        let bytes = format.encode(message)?;
+        if format.is_binary() {
+            let hex: String = bytes.iter().map(|x| format!("{x:02x}")).collect();
+            writeln!(writer, "{hex}")?;
+        } else {
+            writer.write_all(&bytes)?;
+            writeln!(writer)?;
+        }
+    }
+
+    Ok(())
+}
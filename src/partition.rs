@@ -0,0 +1,129 @@
+/// The offset basis of the 32-bit FNV-1a hash.
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+
+/// The prime of the 32-bit FNV-1a hash.
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Pick the partition a customer's messages are written to.
+///
+/// Unlike the hashing of the Kafka client, the assignment is guaranteed to stay the same across
+/// client versions and restarts, as long as the number of partitions doesn't change.
+///
+/// # Arguments
+///
+/// * `customer_id` - The ID of the customer.
+/// * `num_partitions` - The number of partitions of the topic.
+///
+/// # Returns
+///
+/// * The partition, in `0..num_partitions`.
+///
+/// # Panics
+///
+/// * If `num_partitions` isn't positive.
+#[must_use]
+pub fn partition_for(customer_id: u32, num_partitions: i32) -> i32 {
+    assert!(num_partitions > 0, "Partition count must be positive!");
+
+    let partition = fnv1a(&customer_id.to_be_bytes()) % num_partitions.unsigned_abs();
+
+    // The partition is less than `num_partitions`, so it always fits.
+    partition as i32
+}
+
+/// Count how many messages would be written to each partition.
+///
+/// # Arguments
+///
+/// * `customer_ids` - The IDs of the customers of the messages.
+/// * `num_partitions` - The number of partitions of the topic.
+///
+/// # Returns
+///
+/// * The number of messages per partition, indexed by partition.
+///
+/// # Panics
+///
+/// * If `num_partitions` isn't positive.
+#[must_use]
+pub fn distribution(customer_ids: impl IntoIterator<Item = u32>, num_partitions: i32) -> Vec<u64> {
+    assert!(num_partitions > 0, "Partition count must be positive!");
+
+    let mut counts = vec![0; num_partitions.unsigned_abs() as usize];
+    for customer_id in customer_ids {
+        // The partition is in `0..num_partitions`, so it's never negative.
+        counts[partition_for(customer_id, num_partitions) as usize] += 1;
+    }
+
+    counts
+}
+
+/// Measure how unevenly messages are spread across partitions.
+///
+/// # Arguments
+///
+/// * `counts` - The number of messages per partition.
+///
+/// # Returns
+///
+/// * The ratio of the fullest partition to the average, `1.0` being perfectly even, or `None` if
+///   there are no messages.
+#[must_use]
+pub fn skew(counts: &[u64]) -> Option<f64> {
+    let total: u64 = counts.iter().sum();
+    let max = counts.iter().max()?;
+    if total == 0 {
+        return None;
+    }
+
+    Some(*max as f64 * counts.len() as f64 / total as f64)
+}
+
+/// Pick the topic a customer's messages are written to, sharding them across several topics.
+///
+/// # Arguments
+///
+/// * `topic` - The base name of the topics.
+/// * `customer_id` - The ID of the customer.
+/// * `shards` - The number of topics to shard across, `1` to always use the base topic.
+///
+/// # Returns
+///
+/// * The base topic if there's a single shard, otherwise the base topic suffixed with
+///   `_{customer_id % shards}`.
+///
+/// # Panics
+///
+/// * If `shards` is zero.
+#[must_use]
+pub fn topic_for(topic: &str, customer_id: u32, shards: u32) -> String {
+    assert!(shards > 0, "Topic shard count must be positive!");
+
+    if shards == 1 {
+        return topic.to_string();
+    }
+
+    format!("{topic}_{}", customer_id % shards)
+}
+
+/// Hash bytes with the 32-bit [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) hash.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes to hash.
+///
+/// # Returns
+///
+/// * The hash of the bytes.
+const fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash = FNV_OFFSET_BASIS;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+
+    hash
+}
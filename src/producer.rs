@@ -0,0 +1,864 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use rand::Rng;
+use rdkafka::{
+    error::KafkaError,
+    message::{Header, OwnedHeaders, ToBytes},
+    producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer},
+    ClientConfig,
+};
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+#[cfg(feature = "schema-registry")]
+use crate::registry;
+use crate::{
+    compression::{Compression, CONTENT_ENCODING_HEADER},
+    config::DEFAULT_MAX_BYTES,
+    enrich::{Enriched, Enricher},
+    envelope::CURRENT_VERSION,
+    error::ProducerError,
+    format::{Format, CONTENT_TYPE_HEADER, SCHEMA_VERSION_HEADER},
+    partition::{partition_for, topic_for},
+    timestamp::{TimestampUnit, TIMESTAMP_UNIT_HEADER},
+    Message,
+};
+
+/// How long to wait before retrying a message rejected by a full queue, doubled on every retry.
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// The longest wait between retries of a message rejected by a full queue.
+const MAX_QUEUE_FULL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest wait between retries of a message that failed to be delivered.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long [`create_producer`] waits for the brokers to respond.
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The outcome of a successfully delivered message.
+///
+/// # Fields
+///
+/// * `partition` - The partition the message was written to.
+/// * `offset` - The offset of the message within its partition.
+/// * `latency` - The time from enqueueing the message until it was acknowledged, including any
+///   time spent waiting for the queue to drain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+    pub partition: i32,
+    pub offset: i64,
+    pub latency: Duration,
+}
+
+/// The layout of the cluster, as fetched when the producer was created.
+///
+/// # Fields
+///
+/// * `brokers` - The number of brokers in the cluster.
+/// * `partitions` - The fewest partitions of any of the topics, so that no customer is pinned to a
+///   partition beyond them, `None` if none of them exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClusterMetadata {
+    pub brokers: usize,
+    pub partitions: Option<usize>,
+}
+
+/// A message handed to the Kafka client, awaiting its delivery.
+struct PendingDelivery {
+    future: DeliveryFuture,
+    enqueued_at: Instant,
+}
+
+/// A producer of `Message`s to a single Kafka topic.
+///
+/// Cloning is cheap, as all clones share the same underlying client.
+#[derive(Clone)]
+pub struct MessageProducer {
+    producer: FutureProducer,
+    topic: String,
+    format: Format,
+    compression: Compression,
+    max_bytes: usize,
+    timestamp_unit: TimestampUnit,
+    keyed: bool,
+    enricher: Option<Arc<dyn Enricher>>,
+    partitions: Option<i32>,
+    topic_shards: u32,
+    #[cfg(feature = "schema-registry")]
+    schema_id: Option<u32>,
+}
+
+impl MessageProducer {
+    /// Construct a new `MessageProducer` instance, blocking until the brokers respond.
+    ///
+    /// # Arguments
+    ///
+    /// * `brokers` - The Kafka brokers to connect to.
+    /// * `topic` - The topic to produce messages to.
+    /// * `overrides` - Extra Kafka client properties, see [`create_producer`].
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `MessageProducer`.
+    ///
+    /// # Errors
+    ///
+    /// * If the Kafka client couldn't be created.
+    /// * If the brokers didn't respond.
+    pub fn new(
+        brokers: &[String],
+        topic: impl Into<String>,
+        overrides: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let producer = create_producer(&brokers.join(","), overrides)?;
+
+        Ok(Self::from_client(producer, topic))
+    }
+
+    /// Construct a new `MessageProducer` instance from a Kafka client, e.g. one created with
+    /// [`create_producer_with_metadata`].
+    ///
+    /// # Arguments
+    ///
+    /// * `producer` - The Kafka client to produce with.
+    /// * `topic` - The topic to produce messages to.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `MessageProducer`.
+    #[must_use]
+    pub fn from_client(producer: FutureProducer, topic: impl Into<String>) -> Self {
+        Self {
+            producer,
+            topic: topic.into(),
+            format: Format::default(),
+            compression: Compression::default(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            timestamp_unit: TimestampUnit::default(),
+            keyed: true,
+            enricher: None,
+            partitions: None,
+            topic_shards: 1,
+            #[cfg(feature = "schema-registry")]
+            schema_id: None,
+        }
+    }
+
+    /// Set the wire format messages are encoded in, defaulting to JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format to encode messages in.
+    #[must_use]
+    pub const fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+
+        self
+    }
+
+    /// Set the compression applied to the encoded messages, defaulting to none.
+    ///
+    /// # Arguments
+    ///
+    /// * `compression` - The compression to apply.
+    #[must_use]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+
+        self
+    }
+
+    /// Set the largest encoded payload to send, defaulting to [`DEFAULT_MAX_BYTES`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - The largest payload in bytes, see [`check_payload_size`].
+    #[must_use]
+    pub const fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+
+        self
+    }
+
+    /// Set the enricher attaching extra fields to the messages, see [`Enriched`], defaulting to
+    /// none so that messages are sent as is.
+    ///
+    /// Only the JSON format can carry extra fields, the other formats ignore them.
+    ///
+    /// # Arguments
+    ///
+    /// * `enricher` - The enricher to use.
+    #[must_use]
+    pub fn with_enricher(mut self, enricher: Arc<dyn Enricher>) -> Self {
+        self.enricher = Some(enricher);
+
+        self
+    }
+
+    /// Set the unit named by the [`TIMESTAMP_UNIT_HEADER`], defaulting to milliseconds.
+    ///
+    /// The messages are sent as given, so their timestamps must already be in the `unit`, see
+    /// [`TimestampUnit::apply`].
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The unit of the timestamps.
+    #[must_use]
+    pub const fn with_timestamp_unit(mut self, unit: TimestampUnit) -> Self {
+        self.timestamp_unit = unit;
+
+        self
+    }
+
+    /// Set whether messages are produced with their key, see [`Message::key`], defaulting to
+    /// keyed.
+    ///
+    /// How unkeyed messages are partitioned is up to the client properties, see
+    /// [`Keying::properties`](crate::config::Keying::properties).
+    ///
+    /// # Arguments
+    ///
+    /// * `keyed` - Whether the messages are keyed.
+    #[must_use]
+    pub const fn with_keys(mut self, keyed: bool) -> Self {
+        self.keyed = keyed;
+
+        self
+    }
+
+    /// Assign each customer a fixed partition with [`partition_for`], instead of leaving it to the
+    /// Kafka client.
+    ///
+    /// # Arguments
+    ///
+    /// * `partitions` - The number of partitions of the topic, `None` to use the Kafka client.
+    #[must_use]
+    pub const fn with_partitions(mut self, partitions: Option<i32>) -> Self {
+        self.partitions = partitions;
+
+        self
+    }
+
+    /// Shard the messages across several topics by customer, see [`topic_for`].
+    ///
+    /// # Arguments
+    ///
+    /// * `shards` - The number of topics to shard across, `1` to only use the base topic.
+    #[must_use]
+    pub const fn with_topic_shards(mut self, shards: u32) -> Self {
+        self.topic_shards = shards;
+
+        self
+    }
+
+    /// Frame every payload for the schema registry, see [`registry::frame`].
+    ///
+    /// # Arguments
+    ///
+    /// * `schema_id` - The ID of the schema the payloads are encoded with, `None` to leave them
+    ///   unframed.
+    #[cfg(feature = "schema-registry")]
+    #[must_use]
+    pub const fn with_schema_id(mut self, schema_id: Option<u32>) -> Self {
+        self.schema_id = schema_id;
+
+        self
+    }
+
+    /// Encode and send a message, waiting for it to be delivered.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to send.
+    ///
+    /// # Returns
+    ///
+    /// * Where and how quickly the message was delivered.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be encoded.
+    /// * If the message couldn't be enqueued or delivered.
+    pub async fn send(&self, message: &Message) -> Result<Delivery, ProducerError> {
+        let mut buf = String::with_capacity(message.size_hint());
+        let delivery = self.enqueue(message, &mut buf).await?;
+
+        await_delivery(delivery).await
+    }
+
+    /// Encode and send a batch of messages, only waiting for their delivery once all of them are
+    /// enqueued.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send.
+    ///
+    /// # Returns
+    ///
+    /// * The delivery of each message, or the reason it wasn't delivered.
+    pub async fn send_batch(&self, messages: &[Message]) -> Vec<Result<Delivery, ProducerError>> {
+        // Reusing a single buffer for the JSON payloads rather than allocating one per message.
+        let mut buf = String::with_capacity(messages.first().map_or(0, Message::size_hint));
+        let mut deliveries = Vec::with_capacity(messages.len());
+        for message in messages {
+            deliveries.push(self.enqueue(message, &mut buf).await);
+        }
+
+        let mut results = Vec::with_capacity(deliveries.len());
+        for delivery in deliveries {
+            results.push(match delivery {
+                Ok(v) => await_delivery(v).await,
+                Err(e) => Err(e),
+            });
+        }
+
+        results
+    }
+
+    /// Send a tombstone for a customer, waiting for it to be delivered.
+    ///
+    /// A tombstone is a record with the customer's key but no payload, which log compaction
+    /// treats as a deletion: once compacted, every earlier message of the customer is removed,
+    /// and the tombstone itself after the `delete.retention.ms` of the topic. It's always keyed
+    /// and partitioned like the customer's messages, whatever the keying.
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_id` - The ID of the customer to delete.
+    ///
+    /// # Returns
+    ///
+    /// * Where and how quickly the tombstone was delivered.
+    ///
+    /// # Errors
+    ///
+    /// * If the tombstone couldn't be enqueued or delivered.
+    pub async fn send_tombstone(&self, customer_id: u32) -> Result<Delivery, ProducerError> {
+        let key = customer_id.to_string();
+        let topic = self.topic_for(customer_id);
+
+        let mut record: FutureRecord<'_, String, [u8]> = FutureRecord::to(&topic).key(&key);
+        if let Some(partitions) = self.partitions {
+            record = record.partition(partition_for(customer_id, partitions));
+        }
+
+        let delivery = self.hand_over(record).await?;
+
+        await_delivery(delivery).await
+    }
+
+    /// Encode a message and hand it to the Kafka client, backing off while its queue is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to enqueue.
+    /// * `buf` - The buffer to encode JSON payloads into, reused between messages.
+    ///
+    /// # Returns
+    ///
+    /// * The message awaiting its delivery.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be encoded.
+    /// * If the encoded message is larger than the `max_bytes`.
+    /// * If the message couldn't be enqueued.
+    async fn enqueue(
+        &self,
+        message: &Message,
+        buf: &mut String,
+    ) -> Result<PendingDelivery, ProducerError> {
+        let payload = self
+            .encode(message, buf)
+            .map_err(|e| ProducerError::Serialization(format!("{e:#}")))?;
+        check_payload_size(&payload, self.max_bytes)?;
+        let key = message.key();
+        let topic = self.topic_for(message.customer_id());
+
+        let mut record = FutureRecord::to(&topic).payload(payload.as_ref());
+        if self.keyed {
+            record = record.key(&key);
+        }
+        if let Some(partitions) = self.partitions {
+            record = record.partition(partition_for(message.customer_id(), partitions));
+        }
+
+        let schema_version = CURRENT_VERSION.to_string();
+        let mut headers = OwnedHeaders::new()
+            .insert(Header {
+                key: CONTENT_TYPE_HEADER,
+                value: Some(self.format.content_type()),
+            })
+            .insert(Header {
+                key: SCHEMA_VERSION_HEADER,
+                value: Some(&schema_version),
+            })
+            .insert(Header {
+                key: TIMESTAMP_UNIT_HEADER,
+                value: Some(self.timestamp_unit.symbol()),
+            });
+        if let Some(encoding) = self.compression.content_encoding() {
+            headers = headers.insert(Header {
+                key: CONTENT_ENCODING_HEADER,
+                value: Some(encoding),
+            });
+        }
+
+        self.hand_over(record.headers(headers)).await
+    }
+
+    /// Hand a record to the Kafka client, backing off while its queue is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record to enqueue.
+    ///
+    /// # Returns
+    ///
+    /// * The record awaiting its delivery.
+    ///
+    /// # Errors
+    ///
+    /// * If the record couldn't be enqueued.
+    async fn hand_over<K, P>(
+        &self,
+        mut record: FutureRecord<'_, K, P>,
+    ) -> Result<PendingDelivery, ProducerError>
+    where
+        K: ToBytes + ?Sized,
+        P: ToBytes + ?Sized,
+    {
+        let enqueued_at = Instant::now();
+        let mut backoff = QUEUE_FULL_BACKOFF;
+        let future = loop {
+            match self.producer.send_result(record) {
+                Ok(v) => break v,
+                Err((e, returned)) => match ProducerError::from(e) {
+                    ProducerError::QueueFull => {
+                        debug!("Producer queue is full, retrying in {backoff:?}...");
+                        time::sleep(backoff).await;
+
+                        backoff = (backoff * 2).min(MAX_QUEUE_FULL_BACKOFF);
+                        record = returned;
+                    }
+                    e => return Err(e),
+                },
+            }
+        };
+
+        Ok(PendingDelivery {
+            future,
+            enqueued_at,
+        })
+    }
+
+    /// Encode a message into the payload of its record, along with any extra fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to encode.
+    /// * `buf` - The buffer to encode JSON payloads into, reused between messages.
+    ///
+    /// # Returns
+    ///
+    /// * The encoded, framed and compressed payload, borrowed from the `buf` if it's plain JSON.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be encoded or compressed.
+    fn encode<'a>(&self, message: &Message, buf: &'a mut String) -> Result<Cow<'a, [u8]>> {
+        let extra = self
+            .enricher
+            .as_ref()
+            .filter(|_| self.format == Format::Json)
+            .map(|x| x.enrich(message))
+            .filter(|x| !x.is_empty());
+        let payload = match extra {
+            Some(extra) => Cow::Owned(
+                Enriched::new(message.clone(), extra)
+                    .to_json()?
+                    .into_bytes(),
+            ),
+            None if self.format == Format::Json => {
+                message.write_json_into(buf)?;
+
+                Cow::Borrowed(buf.as_bytes())
+            }
+            None => Cow::Owned(self.format.encode(message)?),
+        };
+        #[cfg(feature = "schema-registry")]
+        let payload = match self.schema_id {
+            Some(id) => Cow::Owned(registry::frame(id, &payload)),
+            None => payload,
+        };
+
+        match self.compression {
+            Compression::None => Ok(payload),
+            compression => Ok(Cow::Owned(compression.compress(payload.into_owned())?)),
+        }
+    }
+
+    /// Find the topics messages are produced to that don't exist in the cluster, blocking until
+    /// the brokers respond.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait.
+    ///
+    /// # Returns
+    ///
+    /// * The names of the missing topics, see [`MessageProducer::topics`].
+    ///
+    /// # Errors
+    ///
+    /// * If the brokers didn't respond before the `timeout`.
+    pub fn missing_topics(&self, timeout: Duration) -> Result<Vec<String>> {
+        let metadata = self.producer.client().fetch_metadata(None, timeout)?;
+
+        Ok(self
+            .topics()
+            .into_iter()
+            .filter(|topic| !metadata.topics().iter().any(|x| x.name() == topic))
+            .collect())
+    }
+
+    /// Serve the delivery reports and other events queued by the Kafka client.
+    ///
+    /// The client already polls on a thread of its own, waking up as soon as an event arrives,
+    /// so this is only needed to service it more eagerly.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for an event, `Duration::ZERO` to not block.
+    pub fn poll(&self, timeout: Duration) {
+        self.producer.poll(timeout);
+    }
+
+    /// Wait for all enqueued messages to be delivered.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait.
+    ///
+    /// # Errors
+    ///
+    /// * If the queue couldn't be flushed before the `timeout`.
+    pub fn flush(&self, timeout: Duration) -> Result<()> {
+        self.producer.flush(timeout)?;
+
+        Ok(())
+    }
+
+    /// Get the number of messages still awaiting their delivery, including those not yet sent.
+    ///
+    /// # Returns
+    ///
+    /// * The number of un-acknowledged messages.
+    #[must_use]
+    pub fn in_flight_count(&self) -> i32 {
+        self.producer.in_flight_count()
+    }
+
+    /// Get the topic a customer's messages are produced to.
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_id` - The ID of the customer.
+    ///
+    /// # Returns
+    ///
+    /// * The name of the topic, see [`topic_for`].
+    #[must_use]
+    pub fn topic_for(&self, customer_id: u32) -> String {
+        topic_for(&self.topic, customer_id, self.topic_shards)
+    }
+
+    /// Get every topic messages are produced to, one per shard.
+    ///
+    /// # Returns
+    ///
+    /// * The names of the topics, see [`topic_for`].
+    #[must_use]
+    pub fn topics(&self) -> Vec<String> {
+        (0..self.topic_shards.max(1))
+            .map(|shard| self.topic_for(shard))
+            .collect()
+    }
+
+    /// Get the base topic messages are produced to.
+    ///
+    /// # Returns
+    ///
+    /// * The name of the topic.
+    #[must_use]
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+}
+
+/// Send a message, retrying it with exponential backoff as long as it fails with a retryable
+/// error, see [`ProducerError::is_retryable`].
+///
+/// # Arguments
+///
+/// * `producer` - The producer to send with.
+/// * `message` - The message to send.
+/// * `max_retries` - The most times to retry the message.
+/// * `base_delay` - The time to wait before the first retry, doubled on every retry.
+/// * `cancel` - The token to stop retrying on.
+///
+/// # Returns
+///
+/// * Where and how quickly the message was delivered.
+///
+/// # Errors
+///
+/// * If the message failed with an error that isn't retryable.
+/// * If the message still failed after `max_retries` retries, or when `cancel` was cancelled.
+pub async fn send_with_retry(
+    producer: &MessageProducer,
+    message: &Message,
+    max_retries: u32,
+    base_delay: Duration,
+    cancel: &CancellationToken,
+) -> Result<Delivery, ProducerError> {
+    let result = producer.send(message).await;
+
+    retry_failed(producer, message, result, max_retries, base_delay, cancel).await
+}
+
+/// Retry a message that was already sent once, with exponential backoff as long as it fails with
+/// a retryable error, see [`send_with_retry`].
+///
+/// # Arguments
+///
+/// * `producer` - The producer to send with.
+/// * `message` - The message to send.
+/// * `result` - The outcome of the first send of the message.
+/// * `max_retries` - The most times to retry the message.
+/// * `base_delay` - The time to wait before the first retry, doubled on every retry.
+/// * `cancel` - The token to stop retrying on.
+///
+/// # Returns
+///
+/// * Where and how quickly the message was delivered.
+///
+/// # Errors
+///
+/// * If the message failed with an error that isn't retryable.
+/// * If the message still failed after `max_retries` retries, or when `cancel` was cancelled.
+pub async fn retry_failed(
+    producer: &MessageProducer,
+    message: &Message,
+    mut result: Result<Delivery, ProducerError>,
+    max_retries: u32,
+    base_delay: Duration,
+    cancel: &CancellationToken,
+) -> Result<Delivery, ProducerError> {
+    for attempt in 0..max_retries {
+        match &result {
+            Err(e) if e.is_retryable() => {
+                let delay = retry_delay(base_delay, attempt);
+                debug!(
+                    customer_id = message.customer_id(),
+                    error = %e,
+                    "Retrying message in {delay:?}...",
+                );
+                tokio::select! {
+                    () = time::sleep(delay) => {}
+                    () = cancel.cancelled() => break,
+                }
+            }
+            _ => break,
+        }
+
+        result = producer.send(message).await;
+    }
+
+    result
+}
+
+/// Pick the time to wait before a retry, doubling the base delay on every retry and spreading
+/// the retries of concurrent messages with a random jitter.
+///
+/// # Arguments
+///
+/// * `base_delay` - The time to wait before the first retry.
+/// * `attempt` - The number of retries made so far.
+///
+/// # Returns
+///
+/// * A random delay between half and all of the backoff, capped at [`MAX_RETRY_DELAY`].
+fn retry_delay(base_delay: Duration, attempt: u32) -> Duration {
+    let backoff = base_delay
+        .saturating_mul(2_u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY);
+
+    backoff.mul_f64(rand::rng().random_range(0.5..=1.0))
+}
+
+/// Wait for an enqueued message to be delivered.
+///
+/// # Arguments
+///
+/// * `pending` - The message awaiting its delivery.
+///
+/// # Returns
+///
+/// * Where and how quickly the message was delivered.
+///
+/// # Errors
+///
+/// * If the message couldn't be delivered.
+async fn await_delivery(pending: PendingDelivery) -> Result<Delivery, ProducerError> {
+    let (partition, offset) = pending
+        .future
+        .await
+        .map_err(|_| ProducerError::Fatal(KafkaError::Canceled))?
+        .map_err(|(e, _)| e)?;
+
+    Ok(Delivery {
+        partition,
+        offset,
+        latency: pending.enqueued_at.elapsed(),
+    })
+}
+
+/// Check that an encoded payload isn't larger than the brokers would accept, rather than leaving
+/// them to reject it.
+///
+/// # Arguments
+///
+/// * `payload` - The encoded payload.
+/// * `max_bytes` - The largest payload in bytes.
+///
+/// # Errors
+///
+/// * If the `payload` is larger than `max_bytes`.
+pub const fn check_payload_size(payload: &[u8], max_bytes: usize) -> Result<(), ProducerError> {
+    if payload.len() > max_bytes {
+        return Err(ProducerError::PayloadTooLarge {
+            size: payload.len(),
+            limit: max_bytes,
+        });
+    }
+
+    Ok(())
+}
+
+/// Create a Kafka producer tuned for throughput, blocking until the brokers respond.
+///
+/// # Arguments
+///
+/// * `bootstrap_server` - A comma-separated list of brokers to connect to.
+/// * `overrides` - Extra [client properties](https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md),
+///   applied on top of the defaults.
+///
+/// # Returns
+///
+/// * The created `FutureProducer`.
+///
+/// # Errors
+///
+/// * If the Kafka client couldn't be created.
+/// * If the brokers didn't respond, see [`create_producer_with_metadata`].
+pub fn create_producer(
+    bootstrap_server: &str,
+    overrides: &HashMap<String, String>,
+) -> Result<FutureProducer> {
+    let (producer, _) =
+        create_producer_with_metadata(bootstrap_server, overrides, &[], METADATA_TIMEOUT)?;
+
+    Ok(producer)
+}
+
+/// Create a Kafka producer tuned for throughput, and fetch the layout of the cluster it connected
+/// to, blocking until the brokers respond.
+///
+/// # Arguments
+///
+/// * `bootstrap_server` - A comma-separated list of brokers to connect to.
+/// * `overrides` - Extra [client properties](https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md),
+///   applied on top of the defaults.
+/// * `topics` - The topics to count the partitions of, see [`MessageProducer::topics`].
+/// * `timeout` - The maximum time to wait for the metadata.
+///
+/// # Returns
+///
+/// * The created `FutureProducer`, along with the metadata of the cluster.
+///
+/// # Errors
+///
+/// * If the Kafka client couldn't be created.
+/// * If the brokers didn't respond before the `timeout`.
+pub fn create_producer_with_metadata(
+    bootstrap_server: &str,
+    overrides: &HashMap<String, String>,
+    topics: &[String],
+    timeout: Duration,
+) -> Result<(FutureProducer, ClusterMetadata)> {
+    let producer: FutureProducer = client_config(bootstrap_server, overrides).create()?;
+
+    // Fetching the metadata of every topic, as asking for a missing one may create it.
+    let metadata = producer.client().fetch_metadata(None, timeout)?;
+    let metadata = ClusterMetadata {
+        brokers: metadata.brokers().len(),
+        partitions: metadata
+            .topics()
+            .iter()
+            .filter(|x| topics.iter().any(|topic| x.name() == topic))
+            .map(|x| x.partitions().len())
+            .min(),
+    };
+    debug!(
+        brokers = metadata.brokers,
+        partitions = ?metadata.partitions,
+        "Fetched the cluster metadata.",
+    );
+
+    Ok((producer, metadata))
+}
+
+/// Build the configuration of a Kafka producer tuned for throughput, logging its properties.
+///
+/// # Arguments
+///
+/// * `bootstrap_server` - A comma-separated list of brokers to connect to.
+/// * `overrides` - Extra [client properties](https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md),
+///   applied on top of the defaults.
+///
+/// # Returns
+///
+/// * The configuration of the client.
+fn client_config(bootstrap_server: &str, overrides: &HashMap<String, String>) -> ClientConfig {
+    let mut config = ClientConfig::new();
+    config
+        .set("bootstrap.servers", bootstrap_server)
+        .set("queue.buffering.max.messages", "100000000")
+        .set("queue.buffering.max.ms", "0")
+        .set("batch.num.messages", "100");
+    for (key, value) in overrides {
+        config.set(key, value);
+    }
+
+    let mut properties: Vec<_> = config.config_map().iter().collect();
+    properties.sort_unstable();
+    for (key, value) in properties {
+        // Keep credentials out of the logs.
+        if key.contains("password") || key.contains("secret") {
+            debug!("Kafka client config: {key}=<redacted>");
+        } else {
+            debug!("Kafka client config: {key}={value}");
+        }
+    }
+
+    config
+}
@@ -0,0 +1,201 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{error::ModelError, model::ConsumptionModel, MilliwattHours};
+
+/// The header line naming the columns of a CSV file of profiles.
+pub const PROFILE_CSV_HEADER: &str = "customer_id,base,variance";
+
+/// The stable consumption characteristics of a single customer.
+///
+/// # Fields
+///
+/// * `base` - The consumption the customer's readings are centered around.
+/// * `variance` - The variance of the readings around the `base`, in mWh squared.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ConsumptionProfile {
+    pub base: MilliwattHours,
+    pub variance: f32,
+}
+
+impl ConsumptionProfile {
+    /// Get the model the consumptions of the customer are sampled from.
+    ///
+    /// # Returns
+    ///
+    /// * A normal distribution around the `base`, with the `variance` of the profile.
+    #[must_use]
+    pub fn model(&self) -> ConsumptionModel {
+        ConsumptionModel::Normal {
+            mean: self.base.value(),
+            std: self.variance.sqrt(),
+        }
+    }
+
+    /// Sample a consumption of the customer.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness source to sample with.
+    ///
+    /// # Returns
+    ///
+    /// * The sampled consumption, never negative.
+    ///
+    /// # Errors
+    ///
+    /// * If the `variance` is negative or not finite.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<MilliwattHours, ModelError> {
+        self.model().sample(rng)
+    }
+}
+
+/// A single line of a file of profiles.
+#[derive(Deserialize)]
+struct ProfileEntry {
+    customer_id: u32,
+    #[serde(flatten)]
+    profile: ConsumptionProfile,
+}
+
+/// The consumption profiles of the customers with known characteristics.
+///
+/// # Fields
+///
+/// * `profiles` - The profile of every known customer, by ID.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileRegistry {
+    profiles: HashMap<u32, ConsumptionProfile>,
+}
+
+impl ProfileRegistry {
+    /// Load the profiles from a file, either a CSV file with the columns of
+    /// [`PROFILE_CSV_HEADER`] if it has a `.csv` extension, or a JSON array of objects with the
+    /// same fields otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// * The loaded `ProfileRegistry`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be read, or isn't validly formatted.
+    /// * If a customer has more than one profile.
+    /// * If the variance of a profile is negative or not finite.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}!", path.display()))?;
+
+        let entries = if path.extension().is_some_and(|x| x == "csv") {
+            parse_csv(&contents)
+        } else {
+            serde_json::from_str(&contents).map_err(Into::into)
+        }
+        .with_context(|| format!("Invalid profiles in {}!", path.display()))?;
+
+        let mut profiles = HashMap::with_capacity(entries.len());
+        for ProfileEntry {
+            customer_id,
+            profile,
+        } in entries
+        {
+            profile
+                .model()
+                .validate()
+                .with_context(|| format!("Invalid profile of customer {customer_id}!"))?;
+
+            if profiles.insert(customer_id, profile).is_some() {
+                bail!("Customer {customer_id} has more than one profile!");
+            }
+        }
+
+        Ok(Self { profiles })
+    }
+
+    /// Get the profile of a customer.
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_id` - The ID of the customer.
+    ///
+    /// # Returns
+    ///
+    /// * The profile, or `None` if the customer isn't known.
+    #[must_use]
+    pub fn get(&self, customer_id: u32) -> Option<&ConsumptionProfile> {
+        self.profiles.get(&customer_id)
+    }
+
+    /// Get the number of known customers.
+    ///
+    /// # Returns
+    ///
+    /// * The number of profiles.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// Check whether no customers are known.
+    ///
+    /// # Returns
+    ///
+    /// * Whether there are no profiles.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+}
+
+/// Parse the lines of a CSV file of profiles, skipping its header and any empty lines.
+///
+/// # Arguments
+///
+/// * `contents` - The contents of the file.
+///
+/// # Returns
+///
+/// * The profile on every line.
+///
+/// # Errors
+///
+/// * If a line doesn't have exactly three fields, or a field couldn't be parsed.
+fn parse_csv(contents: &str) -> Result<Vec<ProfileEntry>> {
+    let mut entries = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line == PROFILE_CSV_HEADER {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [customer_id, base, variance] = fields[..] else {
+            bail!("Expected 3 fields on line {}, got {}!", i + 1, fields.len());
+        };
+
+        entries.push(ProfileEntry {
+            customer_id: customer_id
+                .parse()
+                .with_context(|| format!("Invalid customer ID: {customer_id:?}!"))?,
+            profile: ConsumptionProfile {
+                base: MilliwattHours(
+                    base.parse()
+                        .with_context(|| format!("Invalid base: {base:?}!"))?,
+                ),
+                variance: variance
+                    .parse()
+                    .with_context(|| format!("Invalid variance: {variance:?}!"))?,
+            },
+        });
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+/// A target rate of messages per second, rising or falling linearly from a start to an end rate
+/// over a duration, and holding at the end rate afterwards.
+///
+/// # Fields
+///
+/// * `start_rate` - The rate at the start of the ramp.
+/// * `end_rate` - The rate at the end of the ramp, held afterwards.
+/// * `duration` - The time to go from the `start_rate` to the `end_rate`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateRamp {
+    pub start_rate: f64,
+    pub end_rate: f64,
+    pub duration: Duration,
+}
+
+impl RateRamp {
+    /// Construct a new `RateRamp` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_rate` - The rate at the start of the ramp.
+    /// * `end_rate` - The rate at the end of the ramp, held afterwards.
+    /// * `duration` - The time to go from the `start_rate` to the `end_rate`.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `RateRamp`.
+    #[must_use]
+    pub const fn new(start_rate: f64, end_rate: f64, duration: Duration) -> Self {
+        Self {
+            start_rate,
+            end_rate,
+            duration,
+        }
+    }
+
+    /// Construct a `RateRamp` holding a single rate from the start.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The rate to hold.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `RateRamp` without any ramp.
+    #[must_use]
+    pub const fn constant(rate: f64) -> Self {
+        Self::new(rate, rate, Duration::ZERO)
+    }
+
+    /// Scale both rates of the ramp, e.g. to split it between workers.
+    ///
+    /// # Arguments
+    ///
+    /// * `factor` - The factor to multiply the rates by.
+    ///
+    /// # Returns
+    ///
+    /// * The scaled ramp, over the same duration.
+    #[must_use]
+    pub fn scaled(self, factor: f64) -> Self {
+        Self::new(
+            self.start_rate * factor,
+            self.end_rate * factor,
+            self.duration,
+        )
+    }
+
+    /// Get the target rate at a point in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time since the start of the ramp.
+    ///
+    /// # Returns
+    ///
+    /// * The linearly interpolated rate, or the `end_rate` once the ramp is over.
+    #[must_use]
+    pub fn rate_at(&self, elapsed: Duration) -> f64 {
+        if elapsed >= self.duration {
+            return self.end_rate;
+        }
+
+        let progress = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+
+        self.start_rate + (self.end_rate - self.start_rate) * progress
+    }
+}
@@ -0,0 +1,89 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use crate::Message;
+
+/// A fixed-capacity buffer of the most recently produced messages, evicting the oldest ones, to
+/// tell what was just sent without logging every message.
+///
+/// # Fields
+///
+/// * `capacity` - The most messages kept, disabled if `0`.
+/// * `messages` - The kept messages, oldest first.
+#[derive(Debug)]
+pub struct RecentBuffer {
+    capacity: usize,
+    messages: Mutex<VecDeque<Message>>,
+}
+
+impl RecentBuffer {
+    /// Construct a new, empty `RecentBuffer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The most messages to keep, `0` to keep none.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `RecentBuffer`.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Check whether the buffer keeps any messages at all.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the capacity is above zero.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Keep a batch of messages, evicting the oldest ones to make room.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The messages to keep, oldest first.
+    ///
+    /// # Panics
+    ///
+    /// * If the lock is poisoned.
+    pub fn extend(&self, batch: &[Message]) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let skipped = batch.len().saturating_sub(self.capacity);
+        let mut messages = self.messages.lock().expect("Lock poisoned!");
+        for message in &batch[skipped..] {
+            if messages.len() == self.capacity {
+                messages.pop_front();
+            }
+
+            messages.push_back(message.clone());
+        }
+    }
+
+    /// Encode the kept messages as a JSON array, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// * The encoded messages.
+    ///
+    /// # Errors
+    ///
+    /// * If a message couldn't be encoded.
+    ///
+    /// # Panics
+    ///
+    /// * If the lock is poisoned.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let messages = self.messages.lock().expect("Lock poisoned!");
+
+        serde_json::to_string(&*messages)
+    }
+}
@@ -0,0 +1,128 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::format::AVRO_SCHEMA;
+
+/// The content type of requests to the schema registry.
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+
+/// The first byte of every payload framed for the schema registry.
+pub const MAGIC_BYTE: u8 = 0;
+
+/// The body of a schema registration request.
+#[derive(Serialize)]
+struct RegisterRequest<'a> {
+    schema: &'a str,
+}
+
+/// The body of a schema registration response.
+#[derive(Deserialize)]
+struct RegisterResponse {
+    id: u32,
+}
+
+/// A client of a [Confluent Schema Registry](https://docs.confluent.io/platform/current/schema-registry/),
+/// caching the ID of every registered schema.
+///
+/// # Fields
+///
+/// * `client` - The HTTP client to make requests with.
+/// * `url` - The base URL of the registry.
+/// * `ids` - The IDs of the schemas registered so far, by subject.
+#[derive(Debug)]
+pub struct RegistryClient {
+    client: reqwest::Client,
+    url: String,
+    ids: Mutex<HashMap<String, u32>>,
+}
+
+impl RegistryClient {
+    /// Construct a new `RegistryClient` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The base URL of the registry, e.g. `http://localhost:8081`.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `RegistryClient`.
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            ids: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register the Avro schema of a `Message` for the values of a topic, reusing the ID of an
+    /// earlier registration.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic the messages are produced to, registered as `<topic>-value`.
+    ///
+    /// # Returns
+    ///
+    /// * The ID of the schema.
+    ///
+    /// # Errors
+    ///
+    /// * If the registry couldn't be reached or rejected the schema.
+    ///
+    /// # Panics
+    ///
+    /// * If the cache lock is poisoned.
+    pub async fn register(&self, topic: &str) -> Result<u32> {
+        let subject = format!("{topic}-value");
+        if let Some(id) = self.ids.lock().expect("Lock poisoned!").get(&subject) {
+            return Ok(*id);
+        }
+
+        let response: RegisterResponse = self
+            .client
+            .post(format!("{}/subjects/{subject}/versions", self.url))
+            .header("Content-Type", CONTENT_TYPE)
+            .json(&RegisterRequest {
+                schema: AVRO_SCHEMA,
+            })
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to register the schema of {subject}!"))?
+            .json()
+            .await
+            .context("Invalid response from the schema registry!")?;
+        info!("Registered the schema of {subject} as {}.", response.id);
+
+        self.ids
+            .lock()
+            .expect("Lock poisoned!")
+            .insert(subject, response.id);
+
+        Ok(response.id)
+    }
+}
+
+/// Frame a payload for the schema registry, prepending the magic byte and the schema ID.
+///
+/// # Arguments
+///
+/// * `schema_id` - The ID of the schema the payload was encoded with.
+/// * `payload` - The encoded payload.
+///
+/// # Returns
+///
+/// * The framed payload.
+#[must_use]
+pub fn frame(schema_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(5 + payload.len());
+    framed.push(MAGIC_BYTE);
+    framed.extend_from_slice(&schema_id.to_be_bytes());
+    framed.extend_from_slice(payload);
+
+    framed
+}
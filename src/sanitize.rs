@@ -0,0 +1,63 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{error::MessageError, Message, MilliwattHours};
+
+/// How messages with a NaN or infinite consumption are handled before they're sent, as JSON
+/// would otherwise encode their consumption as `null`.
+///
+/// # Variants
+///
+/// * `Zero` - The consumption is replaced with 0 mWh.
+/// * `Reject` - The message is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sanitize {
+    Zero,
+    Reject,
+}
+
+impl Sanitize {
+    /// Sanitize a message, logging a warning if its consumption isn't finite.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to sanitize.
+    ///
+    /// # Returns
+    ///
+    /// * The message, with its consumption replaced with 0 mWh if it wasn't finite and the mode
+    ///   is `Zero`.
+    ///
+    /// # Errors
+    ///
+    /// * If the consumption isn't finite and the mode is `Reject`.
+    pub fn apply(self, message: Message) -> Result<Message, MessageError> {
+        let consumption = message.consumption().value();
+        if consumption.is_finite() {
+            return Ok(message);
+        }
+
+        let customer_id = message.customer_id();
+        match self {
+            Self::Zero => {
+                warn!(
+                    customer_id,
+                    "Replacing the consumption {consumption} with 0 mWh."
+                );
+
+                Ok(Message::new(
+                    customer_id,
+                    MilliwattHours(0.0),
+                    message.timestamp(),
+                ))
+            }
+            Self::Reject => {
+                warn!(customer_id, "Rejecting the consumption {consumption}.");
+
+                Err(MessageError::NonFiniteConsumption(consumption))
+            }
+        }
+    }
+}
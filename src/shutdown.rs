@@ -0,0 +1,35 @@
+use std::future;
+
+use tokio::signal;
+use tracing::error;
+
+/// Wait until the process receives either SIGINT or, on Unix, SIGTERM.
+pub async fn wait_for_signal() {
+    let interrupt = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!("Failed to listen for SIGINT: {e}");
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {e}");
+                future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    tokio::select! {
+        () = interrupt => {},
+        () = terminate => {},
+    }
+}
@@ -0,0 +1,142 @@
+use std::{future::Future, io, path::Path, pin::Pin, slice, sync::Mutex};
+
+use anyhow::Result;
+
+use crate::{
+    format::Format,
+    output::{self, OutputFile},
+    Message,
+};
+
+/// The future returned by the methods of a [`MessageSink`].
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// A destination of produced `Message`s besides Kafka, the counterpart of a
+/// [`MessageSource`](crate::source::MessageSource).
+///
+/// Kafka is produced to through a [`MessageProducer`](crate::producer::MessageProducer) instead,
+/// as its deliveries decide the statistics, the retries and the dead letters.
+///
+/// The futures are boxed so that sinks may be picked at runtime, behind a `dyn MessageSink`.
+pub trait MessageSink: Send + Sync {
+    /// Send a single message.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to send.
+    ///
+    /// # Errors
+    ///
+    /// * If the message couldn't be sent.
+    fn send<'a>(&'a self, message: &'a Message) -> SinkFuture<'a>;
+
+    /// Send a batch of messages, one after the other by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The messages to send.
+    ///
+    /// # Errors
+    ///
+    /// * If any of the messages couldn't be sent.
+    fn send_batch<'a>(&'a self, batch: &'a [Message]) -> SinkFuture<'a> {
+        Box::pin(async move {
+            for message in batch {
+                self.send(message).await?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Flush any messages buffered by the sink.
+    ///
+    /// # Errors
+    ///
+    /// * If the buffered messages couldn't be written.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A sink printing the messages to stdout, one per line, see [`output::write_batch`].
+///
+/// # Fields
+///
+/// * `format` - The format to encode messages in.
+#[derive(Debug, Clone, Copy)]
+pub struct StdoutSink {
+    format: Format,
+}
+
+impl StdoutSink {
+    /// Construct a new `StdoutSink` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format to encode messages in.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `StdoutSink`.
+    #[must_use]
+    pub const fn new(format: Format) -> Self {
+        Self { format }
+    }
+}
+
+impl MessageSink for StdoutSink {
+    fn send<'a>(&'a self, message: &'a Message) -> SinkFuture<'a> {
+        self.send_batch(slice::from_ref(message))
+    }
+
+    fn send_batch<'a>(&'a self, batch: &'a [Message]) -> SinkFuture<'a> {
+        Box::pin(async move { output::write_batch(&mut io::stdout().lock(), batch, self.format) })
+    }
+}
+
+/// A sink appending the messages to a file, see [`OutputFile`].
+///
+/// # Fields
+///
+/// * `file` - The file to append to.
+#[derive(Debug)]
+pub struct FileSink {
+    file: Mutex<OutputFile>,
+}
+
+impl FileSink {
+    /// Open a file to append messages to, see [`OutputFile::open`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    /// * `format` - The format to encode messages in.
+    ///
+    /// # Returns
+    ///
+    /// * The opened `FileSink`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be opened or written to.
+    pub fn open(path: impl AsRef<Path>, format: Format) -> io::Result<Self> {
+        Ok(Self {
+            file: Mutex::new(OutputFile::open(path, format)?),
+        })
+    }
+}
+
+impl MessageSink for FileSink {
+    fn send<'a>(&'a self, message: &'a Message) -> SinkFuture<'a> {
+        self.send_batch(slice::from_ref(message))
+    }
+
+    fn send_batch<'a>(&'a self, batch: &'a [Message]) -> SinkFuture<'a> {
+        Box::pin(async move { self.file.lock().expect("Lock poisoned!").write_batch(batch) })
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(self.file.lock().expect("Lock poisoned!").flush()?)
+    }
+}
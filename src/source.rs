@@ -0,0 +1,748 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Lines},
+    ops::RangeInclusive,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use rand::{seq::index, Rng};
+use rand_distr::{Distribution, Zipf};
+use serde::{Deserialize, Serialize};
+use tokio::time;
+use tracing::{error, info, warn};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    error::{ClockError, ModelError, SourceError},
+    jitter,
+    meter::Meter,
+    model::ConsumptionModel,
+    profile::ProfileRegistry,
+    Message, MilliwattHours,
+};
+
+/// The number of milliseconds in an hour.
+const MILLIS_PER_HOUR: u128 = 3_600_000;
+
+/// The number of milliseconds in a day.
+const MILLIS_PER_DAY: u128 = 24 * MILLIS_PER_HOUR;
+
+/// A source of `Message`s to produce, decoupling the producer from how they originate.
+pub trait MessageSource {
+    /// Get the next message.
+    ///
+    /// # Returns
+    ///
+    /// * The next message, or `None` once the source is exhausted.
+    fn next(&mut self) -> Option<Message>;
+}
+
+impl<S: MessageSource + ?Sized> MessageSource for Box<S> {
+    fn next(&mut self) -> Option<Message> {
+        (**self).next()
+    }
+}
+
+/// The ways the customer of a generated message is picked from the customer range.
+///
+/// # Variants
+///
+/// * `Random` - Picked at random, possibly skewed, see [`RandomSource::with_customer_skew`].
+/// * `Sequential` - Cycling through the range in order, starting at its lowest ID.
+/// * `Hashed` - Cycling through the range in a scrambled order, derived from a counter by a hash,
+///   spreading the IDs uniformly without repeating any within a cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CustomerMode {
+    #[default]
+    Random,
+    Sequential,
+    Hashed,
+}
+
+/// An endless source of randomly generated `Message`s.
+///
+/// # Fields
+///
+/// * `rng` - The randomness source to generate with.
+/// * `model` - The model to sample the consumption from.
+/// * `customer_range` - The range of customer IDs to pick from.
+/// * `timestamp_jitter` - The most milliseconds a timestamp is randomly offset by, in either
+///   direction.
+/// * `clock` - The clock to timestamp the messages with.
+/// * `profiles` - The profiles of customers whose consumption isn't sampled from the `model`.
+/// * `customer_skew` - The distribution of the customers' ranks within the `customer_range`,
+///   picked uniformly if `None`.
+/// * `customer_mode` - How the customers are picked from the `customer_range`.
+/// * `counter` - The number of customers picked so far, for the non-random `customer_mode`s.
+#[derive(Debug, Clone)]
+pub struct RandomSource<R> {
+    rng: R,
+    model: ConsumptionModel,
+    customer_range: RangeInclusive<u32>,
+    timestamp_jitter: u64,
+    clock: Arc<dyn Clock>,
+    profiles: Option<Arc<ProfileRegistry>>,
+    customer_skew: Option<Zipf<f64>>,
+    customer_mode: CustomerMode,
+    counter: u64,
+}
+
+impl<R: Rng> RandomSource<R> {
+    /// Construct a new `RandomSource` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness source to generate with.
+    /// * `model` - The model to sample the consumption from.
+    /// * `customer_range` - The range of customer IDs to pick from.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `RandomSource`.
+    ///
+    /// # Errors
+    ///
+    /// * If the parameters of the `model` are invalid.
+    pub fn new(
+        rng: R,
+        model: ConsumptionModel,
+        customer_range: RangeInclusive<u32>,
+    ) -> Result<Self, ModelError> {
+        model.validate()?;
+
+        Ok(Self {
+            rng,
+            model,
+            customer_range,
+            timestamp_jitter: 0,
+            clock: Arc::new(SystemClock),
+            profiles: None,
+            customer_skew: None,
+            customer_mode: CustomerMode::Random,
+            counter: 0,
+        })
+    }
+
+    /// Set how the customers are picked from the customer range, defaulting to
+    /// [`CustomerMode::Random`].
+    ///
+    /// # Arguments
+    ///
+    /// * `customer_mode` - How to pick the customers. Any skew only applies to random picks.
+    ///
+    /// # Returns
+    ///
+    /// * The source, picking customers in the `customer_mode`.
+    #[must_use]
+    pub const fn with_customer_mode(mut self, customer_mode: CustomerMode) -> Self {
+        self.customer_mode = customer_mode;
+
+        self
+    }
+
+    /// Pick the customers from a [Zipf distribution](https://en.wikipedia.org/wiki/Zipf%27s_law)
+    /// over the customer range, so that the lowest IDs are picked far more often than the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - The exponent of the distribution, where `0.0` picks uniformly and larger
+    ///   values concentrate on fewer customers.
+    ///
+    /// # Returns
+    ///
+    /// * The source, picking skewed customers.
+    ///
+    /// # Errors
+    ///
+    /// * If the `alpha` isn't a non-negative, finite number.
+    pub fn with_customer_skew(mut self, alpha: f64) -> Result<Self, SourceError> {
+        if !alpha.is_finite() || alpha < 0.0 {
+            return Err(SourceError::InvalidSkew(alpha));
+        }
+
+        // Picking uniformly without the distribution keeps seeded runs reproducible.
+        let customers = f64::from(self.customer_range.end() - self.customer_range.start()) + 1.0;
+        self.customer_skew = (alpha > 0.0)
+            .then(|| Zipf::new(customers, alpha))
+            .transpose()
+            .map_err(|_| SourceError::InvalidSkew(alpha))?;
+
+        Ok(self)
+    }
+
+    /// Sample the consumption of known customers from their profiles, falling back to the model
+    /// for the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `profiles` - The profiles of the known customers.
+    ///
+    /// # Returns
+    ///
+    /// * The source, sampling from the `profiles`.
+    #[must_use]
+    pub fn with_profiles(mut self, profiles: Arc<ProfileRegistry>) -> Self {
+        self.profiles = Some(profiles);
+
+        self
+    }
+
+    /// Set the clock to timestamp the messages with, defaulting to the [`SystemClock`].
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock to use.
+    ///
+    /// # Returns
+    ///
+    /// * The source, timestamping with the `clock`.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+
+        self
+    }
+
+    /// Offset every generated timestamp by a random number of milliseconds, so that messages
+    /// generated in a tight loop don't all share the same timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_jitter` - The most milliseconds to offset by, in either direction.
+    ///
+    /// # Returns
+    ///
+    /// * The source, jittering its timestamps.
+    #[must_use]
+    pub const fn with_timestamp_jitter(mut self, timestamp_jitter: u64) -> Self {
+        self.timestamp_jitter = timestamp_jitter;
+
+        self
+    }
+}
+
+impl<R: Rng> MessageSource for RandomSource<R> {
+    fn next(&mut self) -> Option<Message> {
+        let customers = u64::from(self.customer_range.end() - self.customer_range.start()) + 1;
+        let index = self.counter % customers;
+        let customer_id = match (self.customer_mode, &self.customer_skew) {
+            // The ranks start at 1 and never exceed the size of the customer range.
+            (CustomerMode::Random, Some(skew)) => {
+                self.customer_range.start() + skew.sample(&mut self.rng) as u32 - 1
+            }
+            (CustomerMode::Random, None) => self.rng.random_range(self.customer_range.clone()),
+            // The index is less than the size of the customer range, so the ID always fits.
+            (CustomerMode::Sequential, _) => self.customer_range.start() + index as u32,
+            (CustomerMode::Hashed, _) => {
+                self.customer_range.start() + scramble(index, customers) as u32
+            }
+        };
+        self.counter = self.counter.wrapping_add(1);
+        let profile = self.profiles.as_ref().and_then(|x| x.get(customer_id));
+        let consumption = match profile {
+            Some(profile) => profile.sample(&mut self.rng),
+            None => self.model.sample(&mut self.rng),
+        };
+        let consumption = match consumption {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    customer_id,
+                    "Stopping generation, failed to sample the consumption: {e}"
+                );
+
+                return None;
+            }
+        };
+        let now = read_clock(self.clock.as_ref())?;
+        let timestamp = jitter(&mut self.rng, now, self.timestamp_jitter);
+
+        Some(Message::new(customer_id, consumption, timestamp))
+    }
+}
+
+/// Map an index to another within the same range, visiting every index exactly once when the
+/// indices are mapped in turn.
+///
+/// The index is mixed by an invertible hash over the smallest power of two holding the range,
+/// and hashed again until it lands within the range.
+///
+/// # Arguments
+///
+/// * `index` - The index to map, in `0..size`.
+/// * `size` - The size of the range.
+///
+/// # Returns
+///
+/// * The mapped index, in `0..size`.
+fn scramble(index: u64, size: u64) -> u64 {
+    let bits = size.next_power_of_two().trailing_zeros();
+    let mask = (1 << bits) - 1;
+
+    let mut x = index;
+    loop {
+        // Multiplying by an odd number and xor-shifting are both invertible modulo a power of two.
+        x = x.wrapping_mul(0x9e37_79b9_7f4a_7c15) & mask;
+        x ^= x >> (bits / 2 + 1);
+        x = x.wrapping_mul(0xbf58_476d_1ce4_e5b9) & mask;
+
+        if x < size {
+            return x;
+        }
+    }
+}
+
+/// An endless source of readings from simulated meters, taken from each meter in turn.
+///
+/// # Fields
+///
+/// * `rng` - The randomness source to generate with.
+/// * `meters` - The meters to read, each with the timestamp of its previous reading.
+/// * `next` - The index of the meter to read next.
+/// * `clock` - The clock to time the readings with.
+#[derive(Debug, Clone)]
+pub struct MeterSource<R> {
+    rng: R,
+    meters: Vec<(Meter, u128)>,
+    next: usize,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: Rng> MeterSource<R> {
+    /// Construct a new `MeterSource` instance, giving every meter a distinct customer.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The randomness source to generate with.
+    /// * `count` - The number of meters to simulate.
+    /// * `customer_range` - The range of customer IDs to pick from.
+    /// * `mean` - The consumption the daily profiles of the meters swing around.
+    /// * `clock` - The clock to time the readings with.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `MeterSource`.
+    ///
+    /// # Errors
+    ///
+    /// * If the `clock` couldn't tell the time.
+    ///
+    /// # Panics
+    ///
+    /// * If the `count` is zero, or larger than the `customer_range`.
+    pub fn new(
+        mut rng: R,
+        count: usize,
+        customer_range: &RangeInclusive<u32>,
+        mean: MilliwattHours,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, ClockError> {
+        assert!(count > 0, "Meter count must be positive!");
+
+        let start = *customer_range.start();
+        let customers = (customer_range.end() - start) as usize + 1;
+
+        let now = clock.now_millis()?;
+        // Start the meters at the current time of day, in UTC.
+        let hour = (now % MILLIS_PER_DAY) as f32 / MILLIS_PER_HOUR as f32;
+        let meters = index::sample(&mut rng, customers, count)
+            .into_iter()
+            .map(|i| {
+                // The index is within the customer range, so it always fits.
+                let customer_id = start + i as u32;
+
+                (Meter::new(customer_id, mean, hour), now)
+            })
+            .collect();
+
+        Ok(Self {
+            rng,
+            meters,
+            next: 0,
+            clock,
+        })
+    }
+}
+
+impl<R: Rng> MessageSource for MeterSource<R> {
+    fn next(&mut self) -> Option<Message> {
+        let next = self.next;
+        self.next = (next + 1) % self.meters.len();
+
+        let now = read_clock(self.clock.as_ref())?;
+        let (meter, last_timestamp) = &mut self.meters[next];
+        let dt = Duration::from_millis(u64::try_from(now - *last_timestamp).unwrap_or(u64::MAX));
+        *last_timestamp = now;
+
+        let consumption = meter.next_reading(&mut self.rng, dt);
+
+        Some(Message::new(meter.customer_id(), consumption, now))
+    }
+}
+
+/// A source replaying recorded `Message`s from a [JSON Lines](https://jsonlines.org) file, in order.
+///
+/// # Fields
+///
+/// * `lines` - The remaining lines of the file.
+/// * `line_number` - The number of the last line read.
+#[derive(Debug)]
+pub struct FileReplaySource {
+    lines: Lines<BufReader<File>>,
+    line_number: usize,
+}
+
+impl FileReplaySource {
+    /// Open a file to replay.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the JSON Lines file, holding one message per line.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `FileReplaySource`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+            line_number: 0,
+        })
+    }
+}
+
+impl MessageSource for FileReplaySource {
+    fn next(&mut self) -> Option<Message> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("Stopping replay after line {}: {e}", self.line_number);
+                    return None;
+                }
+            };
+            self.line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match Message::from_json(line.as_bytes()) {
+                Ok(v) => return Some(v),
+                Err(e) => warn!("Skipping malformed line {}: {e}", self.line_number),
+            }
+        }
+    }
+}
+
+/// A source ingesting historical `Message`s from a CSV file, in order, with the columns
+/// `customer_id`, `consumption` and `timestamp`, see [`Message::from_csv_record`].
+///
+/// A leading header line is skipped. Invalid rows are logged and counted, and a summary is logged
+/// once the file is exhausted.
+///
+/// # Fields
+///
+/// * `lines` - The remaining lines of the file.
+/// * `line_number` - The number of the last line read.
+/// * `ingested` - The number of rows ingested so far.
+/// * `invalid` - The number of invalid rows skipped so far.
+/// * `exhausted` - Whether the end of the file was reached, and the summary logged.
+#[derive(Debug)]
+pub struct CsvIngestSource {
+    lines: Lines<BufReader<File>>,
+    line_number: usize,
+    ingested: u64,
+    invalid: u64,
+    exhausted: bool,
+}
+
+impl CsvIngestSource {
+    /// Open a CSV file to ingest.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the CSV file, holding one message per row.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `CsvIngestSource`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file couldn't be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+            line_number: 0,
+            ingested: 0,
+            invalid: 0,
+            exhausted: false,
+        })
+    }
+
+    /// Stop ingesting, logging how many rows were ingested and skipped.
+    fn finish(&mut self) {
+        self.exhausted = true;
+        info!(
+            "Ingested {} rows after line {}, skipping {} invalid rows.",
+            self.ingested, self.line_number, self.invalid,
+        );
+    }
+}
+
+impl MessageSource for CsvIngestSource {
+    fn next(&mut self) -> Option<Message> {
+        if self.exhausted {
+            return None;
+        }
+
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(v)) => v,
+                Some(Err(e)) => {
+                    warn!("Stopping ingestion after line {}: {e}", self.line_number);
+                    self.finish();
+
+                    return None;
+                }
+                None => {
+                    self.finish();
+
+                    return None;
+                }
+            };
+            self.line_number += 1;
+
+            let line = line.trim();
+            if line.is_empty() || (self.line_number == 1 && line.starts_with("customer_id")) {
+                continue;
+            }
+
+            match Message::from_csv_record(line) {
+                Ok(v) => {
+                    self.ingested += 1;
+
+                    return Some(v);
+                }
+                Err(e) => {
+                    self.invalid += 1;
+                    warn!("Skipping invalid row on line {}: {e:#}", self.line_number);
+                }
+            }
+        }
+    }
+}
+
+/// A source dropping the messages of another source whose timestamps are too far in the future,
+/// see [`Message::is_plausible`].
+///
+/// # Fields
+///
+/// * `inner` - The source to filter.
+/// * `max_skew_ms` - The number of milliseconds a timestamp may be ahead of the current time.
+/// * `clock` - The clock telling the current time.
+#[derive(Debug, Clone)]
+pub struct FutureSkewFilter<S> {
+    inner: S,
+    max_skew_ms: u128,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: MessageSource> FutureSkewFilter<S> {
+    /// Construct a new `FutureSkewFilter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The source to filter.
+    /// * `max_skew_ms` - The number of milliseconds a timestamp may be ahead of the current time.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `FutureSkewFilter`.
+    #[must_use]
+    pub fn new(inner: S, max_skew_ms: u128) -> Self {
+        Self {
+            inner,
+            max_skew_ms,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the clock telling the current time, defaulting to the [`SystemClock`].
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock to use.
+    ///
+    /// # Returns
+    ///
+    /// * The filter, comparing against the `clock`.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+
+        self
+    }
+}
+
+impl<S: MessageSource> MessageSource for FutureSkewFilter<S> {
+    fn next(&mut self) -> Option<Message> {
+        loop {
+            let message = self.inner.next()?;
+
+            let now = read_clock(self.clock.as_ref())?;
+            if message.is_plausible(now, self.max_skew_ms) {
+                return Some(message);
+            }
+
+            warn!(
+                "Dropping message from customer {}, {} ms in the future.",
+                message.customer_id(),
+                message.timestamp() - now,
+            );
+        }
+    }
+}
+
+/// A source dropping the messages of another source whose customer IDs aren't allowed by the
+/// schema, see [`Message::validate_customer_id`].
+///
+/// # Fields
+///
+/// * `inner` - The source to filter.
+#[derive(Debug, Clone)]
+pub struct CustomerIdFilter<S> {
+    inner: S,
+}
+
+impl<S: MessageSource> CustomerIdFilter<S> {
+    /// Construct a new `CustomerIdFilter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The source to filter.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `CustomerIdFilter`.
+    #[must_use]
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: MessageSource> MessageSource for CustomerIdFilter<S> {
+    fn next(&mut self) -> Option<Message> {
+        loop {
+            let message = self.inner.next()?;
+            match message.validate_customer_id() {
+                Ok(()) => return Some(message),
+                Err(e) => warn!("Dropping message: {e}"),
+            }
+        }
+    }
+}
+
+/// Read the current time of a clock, ending the source it belongs to if the time can't be told.
+///
+/// # Arguments
+///
+/// * `clock` - The clock to read.
+///
+/// # Returns
+///
+/// * The time, in milliseconds since the [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time),
+///   or `None` if the clock failed.
+fn read_clock(clock: &dyn Clock) -> Option<u128> {
+    match clock.now_millis() {
+        Ok(v) => Some(v),
+        Err(e) => {
+            warn!("Stopping generation: {e}");
+
+            None
+        }
+    }
+}
+
+/// Paces messages by the difference between their timestamps, to preserve the timing of a
+/// recording.
+///
+/// # Fields
+///
+/// * `last_timestamp` - The timestamp of the previously paced message.
+/// * `time_scale` - How many times faster than recorded the messages are paced.
+#[derive(Debug, Clone, Copy)]
+pub struct Pacer {
+    last_timestamp: Option<u128>,
+    time_scale: f64,
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Self {
+            last_timestamp: None,
+            time_scale: 1.0,
+        }
+    }
+}
+
+impl Pacer {
+    /// Create a pacer replaying faster or slower than the recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_scale` - How many times faster than recorded to pace the messages, where `1.0` is
+    ///   real time.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `Pacer`.
+    ///
+    /// # Errors
+    ///
+    /// * If the `time_scale` isn't a positive, finite number.
+    pub fn new(time_scale: f64) -> Result<Self, SourceError> {
+        if !time_scale.is_finite() || time_scale <= 0.0 {
+            return Err(SourceError::InvalidTimeScale(time_scale));
+        }
+
+        Ok(Self {
+            last_timestamp: None,
+            time_scale,
+        })
+    }
+
+    /// Wait for as long as passed between the previous message and this one, divided by the time
+    /// scale.
+    ///
+    /// Messages older than their predecessor aren't delayed at all.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message about to be produced.
+    pub async fn wait(&mut self, message: &Message) {
+        let timestamp = message.timestamp();
+        if let Some(last_timestamp) = self.last_timestamp.replace(timestamp) {
+            let delta = timestamp.saturating_sub(last_timestamp);
+
+            // Saturates rather than panicking on gaps too long to sleep for.
+            time::sleep(
+                Duration::try_from_secs_f64(delta as f64 / 1000.0 / self.time_scale)
+                    .unwrap_or(Duration::MAX),
+            )
+            .await;
+        }
+    }
+}
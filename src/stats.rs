@@ -0,0 +1,347 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use hdrhistogram::Histogram;
+use tokio::time;
+use tracing::info;
+
+/// The upper bounds of the buckets of the latency histogram, in seconds.
+pub const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.002_5, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// The highest latency tracked precisely, in microseconds, higher ones are counted as this.
+const MAX_TRACKED_LATENCY_MICROS: u64 = 3_600_000_000;
+
+/// The number of significant digits the latency percentiles are tracked with.
+const LATENCY_SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Counters shared between everything producing messages.
+///
+/// # Fields
+///
+/// * `produced` - The number of messages that were produced successfully.
+/// * `errors` - The number of messages that failed to be produced.
+/// * `latency` - The time it took to deliver the produced messages.
+/// * `percentiles` - The time it took to deliver the produced messages, in microseconds, precise
+///   enough to compute percentiles from.
+/// * `peak_rate` - The most messages produced within a second, see [`track_peak_rate`].
+#[derive(Debug)]
+pub struct Stats {
+    produced: AtomicU64,
+    errors: AtomicU64,
+    latency: LatencyHistogram,
+    percentiles: Mutex<Histogram<u64>>,
+    peak_rate: AtomicU64,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        let percentiles =
+            Histogram::new_with_bounds(1, MAX_TRACKED_LATENCY_MICROS, LATENCY_SIGNIFICANT_DIGITS)
+                .expect("Latency histogram bounds must be valid!");
+
+        Self {
+            produced: AtomicU64::default(),
+            errors: AtomicU64::default(),
+            latency: LatencyHistogram::default(),
+            percentiles: Mutex::new(percentiles),
+            peak_rate: AtomicU64::default(),
+        }
+    }
+}
+
+/// A histogram of delivery latencies, bucketed by [`LATENCY_BUCKETS`].
+///
+/// # Fields
+///
+/// * `buckets` - The number of observations per bucket, with the last one counting those above
+///   every bound.
+/// * `sum_micros` - The sum of all observations, in microseconds.
+#[derive(Debug, Default)]
+struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+/// A point-in-time copy of a latency histogram.
+///
+/// # Fields
+///
+/// * `buckets` - The cumulative number of observations at or below each of [`LATENCY_BUCKETS`].
+/// * `count` - The total number of observations.
+/// * `sum` - The sum of all observations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySnapshot {
+    pub buckets: [u64; LATENCY_BUCKETS.len()],
+    pub count: u64,
+    pub sum: Duration,
+}
+
+/// The percentiles of the delivery latencies.
+///
+/// # Fields
+///
+/// * `p50` - The median latency.
+/// * `p95` - The latency 95% of the messages were delivered within.
+/// * `p99` - The latency 99% of the messages were delivered within.
+/// * `max` - The highest latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// A report of a whole run.
+///
+/// # Fields
+///
+/// * `produced` - The number of messages that were produced successfully.
+/// * `errors` - The number of messages that failed to be produced.
+/// * `elapsed` - The wall time of the run.
+/// * `peak_rate` - The most messages produced within a second.
+/// * `latency` - The percentiles of the delivery latencies, `None` if none were recorded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunSummary {
+    pub produced: u64,
+    pub errors: u64,
+    pub elapsed: Duration,
+    pub peak_rate: u64,
+    pub latency: Option<LatencySummary>,
+}
+
+impl RunSummary {
+    /// Get the number of messages that were attempted.
+    ///
+    /// # Returns
+    ///
+    /// * The number of produced and failed messages.
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.produced + self.errors
+    }
+
+    /// Get the average number of messages produced per second.
+    ///
+    /// # Returns
+    ///
+    /// * The produced messages divided by the elapsed seconds, `0` if no time elapsed.
+    #[must_use]
+    pub fn average_rate(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+
+        self.produced as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl Stats {
+    /// Count successfully produced messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of messages that were produced.
+    pub fn record_produced(&self, count: u64) {
+        self.produced.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Count a message that failed to be produced.
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record how long it took to deliver a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `latency` - The time from enqueueing the message until it was acknowledged.
+    ///
+    /// # Panics
+    ///
+    /// * If the percentile lock is poisoned.
+    pub fn record_latency(&self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|x| seconds <= *x)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        self.latency.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.latency.sum_micros.fetch_add(micros, Ordering::Relaxed);
+        self.percentiles
+            .lock()
+            .expect("Lock poisoned!")
+            .saturating_record(micros.max(1));
+    }
+
+    /// Record the number of messages produced within a second, keeping the highest.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The number of messages produced within the last second.
+    pub fn record_rate(&self, rate: u64) {
+        self.peak_rate.fetch_max(rate, Ordering::Relaxed);
+    }
+
+    /// Summarize a whole run.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The wall time of the run.
+    ///
+    /// # Returns
+    ///
+    /// * The counts, throughput and latency percentiles of the run.
+    ///
+    /// # Panics
+    ///
+    /// * If the percentile lock is poisoned.
+    #[must_use]
+    pub fn summary(&self, elapsed: Duration) -> RunSummary {
+        RunSummary {
+            produced: self.produced(),
+            errors: self.errors(),
+            elapsed,
+            peak_rate: self.peak_rate.load(Ordering::Relaxed),
+            latency: self.latency_summary(),
+        }
+    }
+
+    /// Get the number of messages that were produced successfully.
+    ///
+    /// # Returns
+    ///
+    /// * The total number of produced messages.
+    #[must_use]
+    pub fn produced(&self) -> u64 {
+        self.produced.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of messages that failed to be produced.
+    ///
+    /// # Returns
+    ///
+    /// * The total number of failed messages.
+    #[must_use]
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Get a copy of the delivery latency histogram.
+    ///
+    /// # Returns
+    ///
+    /// * The cumulative bucket counts, total count, and sum of the latencies.
+    #[must_use]
+    pub fn latency(&self) -> LatencySnapshot {
+        let mut buckets = [0; LATENCY_BUCKETS.len()];
+        let mut count = 0;
+        for (i, bucket) in self.latency.buckets.iter().enumerate() {
+            count += bucket.load(Ordering::Relaxed);
+            if let Some(cumulative) = buckets.get_mut(i) {
+                *cumulative = count;
+            }
+        }
+
+        LatencySnapshot {
+            buckets,
+            count,
+            sum: Duration::from_micros(self.latency.sum_micros.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Summarize the delivery latencies by their percentiles.
+    ///
+    /// # Returns
+    ///
+    /// * The percentiles, or `None` if no latency was recorded.
+    ///
+    /// # Panics
+    ///
+    /// * If the percentile lock is poisoned.
+    #[must_use]
+    pub fn latency_summary(&self) -> Option<LatencySummary> {
+        let percentiles = self.percentiles.lock().expect("Lock poisoned!");
+        if percentiles.is_empty() {
+            return None;
+        }
+
+        let at = |quantile| Duration::from_micros(percentiles.value_at_quantile(quantile));
+
+        Some(LatencySummary {
+            p50: at(0.5),
+            p95: at(0.95),
+            p99: at(0.99),
+            max: Duration::from_micros(percentiles.max()),
+        })
+    }
+}
+
+/// Record the number of messages produced every second as a rate, forever, see
+/// [`Stats::record_rate`].
+///
+/// # Arguments
+///
+/// * `stats` - The counters to track the peak rate of.
+pub async fn track_peak_rate(stats: Arc<Stats>) {
+    let mut interval = time::interval(Duration::from_secs(1));
+    interval.tick().await;
+
+    let mut last_total = stats.produced();
+    loop {
+        interval.tick().await;
+
+        let total = stats.produced();
+        stats.record_rate(total - last_total);
+        last_total = total;
+    }
+}
+
+/// Periodically log the throughput and the total number of produced messages, forever.
+///
+/// # Arguments
+///
+/// * `stats` - The counters to report.
+/// * `period` - The time between reports.
+/// * `warmup` - The time to wait before the first report, ignoring the messages produced in the
+///   meantime.
+pub async fn report(stats: Arc<Stats>, period: Duration, warmup: Duration) {
+    if !warmup.is_zero() {
+        info!("Warming up for {warmup:?}...");
+        time::sleep(warmup).await;
+
+        info!(
+            "Warmup complete, ignoring the {} messages produced so far.",
+            stats.produced(),
+        );
+    }
+
+    let baseline = stats.produced();
+    let mut interval = time::interval(period);
+    interval.tick().await;
+
+    let mut last_total = 0;
+    let mut last_report = Instant::now();
+    loop {
+        interval.tick().await;
+
+        let total = stats.produced() - baseline;
+        let elapsed = last_report.elapsed();
+        info!(
+            "Producing {:.0} messages per second, {total} messages in total.",
+            (total - last_total) as f64 / elapsed.as_secs_f64(),
+        );
+
+        last_total = total;
+        last_report = Instant::now();
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use rand::Rng;
+
+use crate::{
+    error::ModelError,
+    model::ConsumptionModel,
+    source::{MessageSource, RandomSource},
+    Message, CUSTOMER_ID_RANGE,
+};
+
+/// A stream of the messages of a source, to compose it with async operators like `map`,
+/// `buffer` or `take`.
+///
+/// Every message is ready as soon as it's polled, so the stream never waits by itself and should
+/// be paced, e.g. with [`tokio::time::interval`], when a rate is wanted.
+///
+/// # Fields
+///
+/// * `source` - The source of the messages.
+#[derive(Debug)]
+pub struct SourceStream<S> {
+    source: S,
+}
+
+impl<S> SourceStream<S> {
+    /// Construct a new `SourceStream` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the messages.
+    ///
+    /// # Returns
+    ///
+    /// * A new instance of `SourceStream`, ending once the `source` is exhausted.
+    #[must_use]
+    pub const fn new(source: S) -> Self {
+        Self { source }
+    }
+
+    /// Unwrap the source of the messages.
+    ///
+    /// # Returns
+    ///
+    /// * The source.
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: MessageSource + Unpin> Stream for SourceStream<S> {
+    type Item = Message;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Message>> {
+        Poll::Ready(self.get_mut().source.next())
+    }
+}
+
+/// Stream randomly generated messages, with customers in the [`CUSTOMER_ID_RANGE`].
+///
+/// # Arguments
+///
+/// * `rng` - The randomness source to generate with.
+/// * `model` - The model to sample the consumption from.
+///
+/// # Returns
+///
+/// * An endless stream of messages, see [`SourceStream`].
+///
+/// # Errors
+///
+/// * If the parameters of the `model` are invalid.
+pub fn message_stream<R: Rng + Unpin>(
+    rng: R,
+    model: ConsumptionModel,
+) -> Result<impl Stream<Item = Message>, ModelError> {
+    Ok(SourceStream::new(RandomSource::new(
+        rng,
+        model,
+        CUSTOMER_ID_RANGE,
+    )?))
+}
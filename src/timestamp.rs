@@ -0,0 +1,139 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+/// The record header naming the unit of the timestamps in the payload.
+pub const TIMESTAMP_UNIT_HEADER: &str = "timestamp-unit";
+
+/// The units the timestamps of sent messages are in, all since the
+/// [Unix Epoch](https://en.wikipedia.org/wiki/Unix_time), see [`TIMESTAMP_UNIT_HEADER`].
+///
+/// Messages are timestamped in milliseconds, so seconds are rounded down and microseconds are
+/// always a multiple of 1000.
+///
+/// # Variants
+///
+/// * `Seconds` - The timestamps are in seconds.
+/// * `Millis` - The timestamps are in milliseconds.
+/// * `Micros` - The timestamps are in microseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+pub enum TimestampUnit {
+    #[value(name = "s")]
+    #[serde(rename = "s")]
+    Seconds,
+    #[default]
+    #[value(name = "ms")]
+    #[serde(rename = "ms")]
+    Millis,
+    #[value(name = "us")]
+    #[serde(rename = "us")]
+    Micros,
+}
+
+impl TimestampUnit {
+    /// Convert a timestamp in milliseconds to this unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The timestamp, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * The timestamp in this unit, saturating at `u128::MAX`.
+    #[must_use]
+    pub const fn from_millis(self, timestamp: u128) -> u128 {
+        match self {
+            Self::Seconds => timestamp / 1_000,
+            Self::Millis => timestamp,
+            Self::Micros => timestamp.saturating_mul(1_000),
+        }
+    }
+
+    /// Convert a timestamp in this unit to milliseconds, the inverse of
+    /// [`TimestampUnit::from_millis`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The timestamp, in this unit.
+    ///
+    /// # Returns
+    ///
+    /// * The timestamp in milliseconds, saturating at `u128::MAX`.
+    #[must_use]
+    pub const fn to_millis(self, timestamp: u128) -> u128 {
+        match self {
+            Self::Seconds => timestamp.saturating_mul(1_000),
+            Self::Millis => timestamp,
+            Self::Micros => timestamp / 1_000,
+        }
+    }
+
+    /// Convert the timestamp of a message, in milliseconds, to this unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to convert.
+    ///
+    /// # Returns
+    ///
+    /// * The message, timestamped in this unit.
+    #[must_use]
+    pub const fn apply(self, message: Message) -> Message {
+        Message::new(
+            message.customer_id(),
+            message.consumption(),
+            self.from_millis(message.timestamp()),
+        )
+    }
+
+    /// Convert the timestamp of a message in this unit back to milliseconds, the inverse of
+    /// [`TimestampUnit::apply`].
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to convert.
+    ///
+    /// # Returns
+    ///
+    /// * The message, timestamped in milliseconds.
+    #[must_use]
+    pub const fn revert(self, message: Message) -> Message {
+        Message::new(
+            message.customer_id(),
+            message.consumption(),
+            self.to_millis(message.timestamp()),
+        )
+    }
+
+    /// Get the value of the [`TIMESTAMP_UNIT_HEADER`] for this unit.
+    ///
+    /// # Returns
+    ///
+    /// * The symbol of the unit.
+    #[must_use]
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Seconds => "s",
+            Self::Millis => "ms",
+            Self::Micros => "us",
+        }
+    }
+
+    /// Get the unit named by a [`TIMESTAMP_UNIT_HEADER`].
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The value of the header.
+    ///
+    /// # Returns
+    ///
+    /// * The named unit, `None` if it's unknown.
+    #[must_use]
+    pub fn from_symbol(symbol: &[u8]) -> Option<Self> {
+        Self::value_variants()
+            .iter()
+            .copied()
+            .find(|x| x.symbol().as_bytes() == symbol)
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Sub},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The number of mWh in a Wh.
+const MILLIWATT_HOURS_PER_WATT_HOUR: f32 = 1_000.0;
+
+/// The number of mWh in a kWh.
+const MILLIWATT_HOURS_PER_KILOWATT_HOUR: f32 = 1_000_000.0;
+
+/// The number of Wh in a kWh.
+const WATT_HOURS_PER_KILOWATT_HOUR: f32 = 1_000.0;
+
+/// Wrapper type for `f32` when used as mWh.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(description = "An amount of electrical energy, in mWh.")]
+pub struct MilliwattHours(pub f32);
+
+impl MilliwattHours {
+    /// Construct a new `MilliwattHours` instance from Wh.
+    ///
+    /// # Arguments
+    ///
+    /// * `watt_hours` - The amount of energy, in Wh.
+    ///
+    /// # Returns
+    ///
+    /// * The same amount of energy, in mWh.
+    #[must_use]
+    pub const fn from_watt_hours(watt_hours: f32) -> Self {
+        Self(watt_hours * MILLIWATT_HOURS_PER_WATT_HOUR)
+    }
+
+    /// Construct a new `MilliwattHours` instance from kWh.
+    ///
+    /// # Arguments
+    ///
+    /// * `kilowatt_hours` - The amount of energy, in kWh.
+    ///
+    /// # Returns
+    ///
+    /// * The same amount of energy, in mWh.
+    #[must_use]
+    pub const fn from_kilowatt_hours(kilowatt_hours: f32) -> Self {
+        Self(kilowatt_hours * MILLIWATT_HOURS_PER_KILOWATT_HOUR)
+    }
+
+    /// Get the amount of energy.
+    ///
+    /// # Returns
+    ///
+    /// * The amount of energy, in mWh.
+    #[must_use]
+    pub const fn value(&self) -> f32 {
+        self.0
+    }
+
+    /// Convert the energy to Wh.
+    ///
+    /// # Returns
+    ///
+    /// * The amount of energy, in Wh.
+    #[must_use]
+    pub const fn to_watt_hours(&self) -> f32 {
+        self.0 / MILLIWATT_HOURS_PER_WATT_HOUR
+    }
+
+    /// Convert the energy to kWh.
+    ///
+    /// # Returns
+    ///
+    /// * The amount of energy, in kWh.
+    #[must_use]
+    pub const fn to_kilowatt_hours(&self) -> f32 {
+        self.0 / MILLIWATT_HOURS_PER_KILOWATT_HOUR
+    }
+
+    /// Restrict the energy to a range.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The least amount of energy.
+    /// * `max` - The greatest amount of energy.
+    ///
+    /// # Returns
+    ///
+    /// * The energy, or the bound it's beyond. NaN if the energy is NaN.
+    ///
+    /// # Panics
+    ///
+    /// * If `min` is greater than `max`, or either of them is NaN.
+    #[must_use]
+    pub const fn clamp(self, min: Self, max: Self) -> Self {
+        Self(self.0.clamp(min.0, max.0))
+    }
+
+    /// Add two amounts of energy, staying finite rather than overflowing to infinity.
+    ///
+    /// # Arguments
+    ///
+    /// * `rhs` - The energy to add.
+    ///
+    /// # Returns
+    ///
+    /// * The sum, clamped to the largest finite amounts. NaN if either amount is NaN.
+    #[must_use]
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        Self((self.0 + rhs.0).clamp(f32::MIN, f32::MAX))
+    }
+
+    /// Check whether the energy is physically possible.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the energy is finite and not negative.
+    #[must_use]
+    pub const fn is_valid(&self) -> bool {
+        self.0.is_finite() && self.0 >= 0.0
+    }
+}
+
+impl From<f32> for MilliwattHours {
+    fn from(milliwatt_hours: f32) -> Self {
+        Self(milliwatt_hours)
+    }
+}
+
+impl From<MilliwattHours> for f32 {
+    fn from(milliwatt_hours: MilliwattHours) -> Self {
+        milliwatt_hours.0
+    }
+}
+
+impl Display for MilliwattHours {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} mWh", self.0)
+    }
+}
+
+impl From<WattHours> for MilliwattHours {
+    fn from(watt_hours: WattHours) -> Self {
+        Self::from_watt_hours(watt_hours.0)
+    }
+}
+
+impl From<KilowattHours> for MilliwattHours {
+    fn from(kilowatt_hours: KilowattHours) -> Self {
+        Self::from_kilowatt_hours(kilowatt_hours.0)
+    }
+}
+
+impl Add for MilliwattHours {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for MilliwattHours {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for MilliwattHours {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Mul<f32> for MilliwattHours {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self(self.0 * rhs)
+    }
+}
+
+impl Sum for MilliwattHours {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self(0.0), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Self> for MilliwattHours {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Wrapper type for `f32` when used as Wh, converted to and from the other units with `into`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WattHours(pub f32);
+
+impl From<MilliwattHours> for WattHours {
+    fn from(milliwatt_hours: MilliwattHours) -> Self {
+        Self(milliwatt_hours.to_watt_hours())
+    }
+}
+
+impl From<KilowattHours> for WattHours {
+    fn from(kilowatt_hours: KilowattHours) -> Self {
+        Self(kilowatt_hours.0 * WATT_HOURS_PER_KILOWATT_HOUR)
+    }
+}
+
+impl Display for WattHours {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} Wh", self.0)
+    }
+}
+
+/// Wrapper type for `f32` when used as kWh, converted to and from the other units with `into`.
+///
+/// The units never convert implicitly, so a kWh value can't be mistaken for mWh:
+///
+/// ```compile_fail
+/// use h4_bigdata::{units::KilowattHours, MilliwattHours};
+///
+/// let consumption: MilliwattHours = KilowattHours(1.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KilowattHours(pub f32);
+
+impl From<MilliwattHours> for KilowattHours {
+    fn from(milliwatt_hours: MilliwattHours) -> Self {
+        Self(milliwatt_hours.to_kilowatt_hours())
+    }
+}
+
+impl From<WattHours> for KilowattHours {
+    fn from(watt_hours: WattHours) -> Self {
+        Self(watt_hours.0 / WATT_HOURS_PER_KILOWATT_HOUR)
+    }
+}
+
+impl Display for KilowattHours {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} kWh", self.0)
+    }
+}
@@ -0,0 +1,50 @@
+use h4_bigdata::config::{Acks, Config, DeliveryGuarantee};
+
+const LINGER: &str = "queue.buffering.max.ms";
+
+#[test]
+fn acks_override_the_delivery_guarantee() {
+    let config = Config {
+        delivery_guarantee: Some(DeliveryGuarantee::AtLeastOnce),
+        acks: Some(Acks::Leader),
+        ..Config::default()
+    };
+
+    assert!(config.validate().is_ok());
+    assert_eq!(config.client_properties()["acks"], "1");
+}
+
+#[test]
+fn linger_sets_the_buffering_time() {
+    let config = Config {
+        linger_ms: Some(20),
+        ..Config::default()
+    };
+
+    assert_eq!(config.client_properties()[LINGER], "20");
+    assert!(!Config::default().client_properties().contains_key(LINGER));
+}
+
+#[test]
+fn kafka_config_overrides_acks() {
+    let mut config = Config {
+        acks: Some(Acks::None),
+        ..Config::default()
+    };
+    config
+        .kafka_config
+        .insert("acks".to_string(), "all".to_string());
+
+    assert_eq!(config.client_properties()["acks"], "all");
+}
+
+#[test]
+fn exactly_once_requires_all_acks() {
+    let config = Config {
+        delivery_guarantee: Some(DeliveryGuarantee::ExactlyOnce),
+        acks: Some(Acks::Leader),
+        ..Config::default()
+    };
+
+    assert!(config.validate().is_err());
+}
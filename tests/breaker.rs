@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+use h4_bigdata::breaker::{Admission, BreakerState, CircuitBreaker};
+
+const COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Open a breaker with a threshold of 3 at a point in time.
+fn opened(now: Instant) -> CircuitBreaker {
+    let breaker = CircuitBreaker::new(3, COOLDOWN);
+    for _ in 0..3 {
+        breaker.record_failure(now);
+    }
+
+    breaker
+}
+
+#[test]
+fn opens_after_consecutive_failures() {
+    let now = Instant::now();
+    let breaker = CircuitBreaker::new(3, COOLDOWN);
+
+    breaker.record_failure(now);
+    breaker.record_failure(now);
+    breaker.record_success();
+    breaker.record_failure(now);
+    breaker.record_failure(now);
+    assert_eq!(breaker.state(), BreakerState::Closed);
+    assert_eq!(breaker.check(now), Admission::Send);
+
+    breaker.record_failure(now);
+    assert_eq!(breaker.state(), BreakerState::Open);
+    assert_eq!(breaker.check(now), Admission::Wait(COOLDOWN));
+}
+
+#[test]
+fn probes_once_the_cooldown_is_over() {
+    let now = Instant::now();
+    let breaker = opened(now);
+
+    assert_eq!(breaker.check(now + COOLDOWN), Admission::Probe);
+    assert_eq!(breaker.state(), BreakerState::HalfOpen);
+    assert!(matches!(breaker.check(now + COOLDOWN), Admission::Wait(_)));
+}
+
+#[test]
+fn closes_on_a_successful_probe() {
+    let now = Instant::now();
+    let breaker = opened(now);
+    breaker.check(now + COOLDOWN);
+
+    breaker.record_success();
+    assert_eq!(breaker.state(), BreakerState::Closed);
+    assert_eq!(breaker.check(now + COOLDOWN), Admission::Send);
+}
+
+#[test]
+fn reopens_on_a_failed_probe() {
+    let now = Instant::now();
+    let breaker = opened(now);
+    let probed_at = now + COOLDOWN;
+    breaker.check(probed_at);
+
+    breaker.record_failure(probed_at);
+    assert_eq!(breaker.state(), BreakerState::Open);
+    assert_eq!(breaker.check(probed_at), Admission::Wait(COOLDOWN));
+}
@@ -0,0 +1,113 @@
+use h4_bigdata::{
+    config::{parse_brokers, Config},
+    format::Format,
+    model::ConsumptionModel,
+};
+
+#[test]
+fn brokers_are_split_and_trimmed() {
+    assert_eq!(
+        parse_brokers("localhost:9092, localhost:9093 ,localhost:9094").unwrap(),
+        ["localhost:9092", "localhost:9093", "localhost:9094"],
+    );
+}
+
+#[test]
+fn empty_brokers_are_skipped() {
+    assert_eq!(
+        parse_brokers("localhost:9092,,").unwrap(),
+        ["localhost:9092"]
+    );
+}
+
+#[test]
+fn broker_list_must_not_be_empty() {
+    assert!(parse_brokers("").is_err());
+    assert!(parse_brokers(" , ").is_err());
+}
+
+#[test]
+fn toml_is_parsed_into_the_config() {
+    let config = Config::from_toml(
+        r#"
+        brokers = ["localhost:9092"]
+        topic = "readings"
+        rate = 500.0
+        format = "msgpack"
+        customer_range = "1000-1099"
+
+        [model]
+        kind = "normal"
+        mean = 5.0
+        std = 1.5
+
+        [kafka_config]
+        "linger.ms" = "5"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.brokers, ["localhost:9092"]);
+    assert_eq!(config.topic, "readings");
+    assert_eq!(config.rate, Some(500.0));
+    assert_eq!(config.format, Format::Msgpack);
+    assert_eq!(config.customer_range, 1_000..=1_099);
+    assert_eq!(
+        config.model,
+        Some(ConsumptionModel::Normal {
+            mean: 5.0,
+            std: 1.5
+        })
+    );
+    assert_eq!(config.kafka_config["linger.ms"], "5");
+    assert_eq!(config.batch_size, Config::default().batch_size);
+    config.validate().unwrap();
+}
+
+#[test]
+fn example_config_is_valid() {
+    let contents = include_str!("../config.example.toml");
+
+    Config::from_toml(contents).unwrap().validate().unwrap();
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    assert!(Config::from_toml("topics = \"readings\"").is_err());
+    assert!(Config::from_toml("sasl_password = \"hunter2\"").is_err());
+}
+
+#[test]
+fn time_scale_requires_a_replay() {
+    let config = Config::from_toml("replay = \"recording.jsonl\"\ntime_scale = 2.0").unwrap();
+    config.validate().unwrap();
+
+    let config = Config::from_toml("time_scale = 2.0").unwrap();
+    assert!(config.validate().is_err());
+
+    let config = Config::from_toml("replay_realtime = true").unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn topics_are_prefixed_and_sharded() {
+    let config = Config {
+        topic: "readings".to_string(),
+        topic_prefix: Some("prod".to_string()),
+        topic_shards: 3,
+        ..Config::default()
+    };
+
+    assert_eq!(
+        config.topics(),
+        ["prod.readings_0", "prod.readings_1", "prod.readings_2"]
+    );
+    assert_eq!(
+        Config {
+            topic_shards: 1,
+            ..config
+        }
+        .topics(),
+        ["prod.readings"]
+    );
+}
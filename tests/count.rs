@@ -0,0 +1,22 @@
+use std::process::Command;
+
+use h4_bigdata::Message;
+
+#[test]
+fn count_stops_after_that_many_messages() {
+    let output = Command::new(env!("CARGO_BIN_EXE_h4-bigdata"))
+        .args(["--count", "10", "--dry-run", "--log-level", "error"])
+        .env_remove("KAFKA_BROKERS")
+        .env_remove("KAFKA_TOPIC")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let messages: Vec<_> = stdout
+        .lines()
+        .map(|x| Message::from_json(x.as_bytes()).unwrap())
+        .collect();
+
+    assert_eq!(messages.len(), 10);
+}
@@ -0,0 +1,51 @@
+use h4_bigdata::{
+    enrich::{Enriched, Enricher, ExtraFields, StaticFields},
+    Message, MilliwattHours,
+};
+use serde::Serialize;
+use serde_json::json;
+
+/// A typed extension, as used to try out new dimensions.
+#[derive(Serialize)]
+struct Dimensions {
+    region: &'static str,
+    meter_type: &'static str,
+}
+
+fn message() -> Message {
+    Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000)
+}
+
+#[test]
+fn extension_is_serialized_alongside_the_message() {
+    let dimensions = Dimensions {
+        region: "north",
+        meter_type: "smart",
+    };
+    let json = Enriched::new(message(), dimensions).to_json().unwrap();
+
+    let mut expected = serde_json::to_value(message()).unwrap();
+    expected["region"] = json!("north");
+    expected["meter_type"] = json!("smart");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+        expected,
+    );
+}
+
+#[test]
+fn no_extras_keep_the_wire_format() {
+    let json = Enriched::new(message(), ExtraFields::new())
+        .to_json()
+        .unwrap();
+
+    assert_eq!(json, message().to_json().unwrap());
+}
+
+#[test]
+fn static_fields_are_attached_to_every_message() {
+    let fields = ExtraFields::from([("region".to_string(), json!("north"))]);
+    let enricher = StaticFields(fields.clone());
+
+    assert_eq!(enricher.enrich(&message()), fields);
+}
@@ -0,0 +1,40 @@
+use h4_bigdata::{
+    envelope::{Envelope, CURRENT_VERSION},
+    error::EnvelopeError,
+    Message, MilliwattHours,
+};
+
+fn message() -> Message {
+    Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000)
+}
+
+#[test]
+fn envelope_round_trips() {
+    let bytes = Envelope::new(message()).encode().unwrap();
+    let envelope = Envelope::<Message>::decode(&bytes).unwrap();
+
+    assert_eq!(envelope.version(), CURRENT_VERSION);
+    assert_eq!(envelope.into_payload(), message());
+}
+
+#[test]
+fn unknown_versions_are_unsupported() {
+    let bytes = br#"{"version":2,"payload":{"something":"else"}}"#;
+
+    assert!(matches!(
+        Envelope::<Message>::decode(bytes),
+        Err(EnvelopeError::UnsupportedVersion(2)),
+    ));
+}
+
+#[test]
+fn malformed_envelopes_are_not_unsupported() {
+    assert!(matches!(
+        Envelope::<Message>::decode(br#"{"payload":{}}"#),
+        Err(EnvelopeError::Json(_)),
+    ));
+    assert!(matches!(
+        Envelope::<Message>::decode(br#"{"version":1,"payload":{}}"#),
+        Err(EnvelopeError::Json(_)),
+    ));
+}
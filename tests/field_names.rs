@@ -0,0 +1,28 @@
+use h4_bigdata::{Message, MilliwattHours};
+
+fn message() -> Message {
+    Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000)
+}
+
+#[test]
+fn camel_case_fields_are_deserialized() {
+    let json = r#"{"customerId":1042,"consumptionMwh":6.5,"timestamp":1700000000000}"#;
+
+    assert_eq!(Message::from_json(json.as_bytes()).unwrap(), message());
+}
+
+#[test]
+fn snake_case_fields_are_deserialized() {
+    let json = r#"{"customer_id":1042,"consumption":6.5,"timestamp":1700000000000}"#;
+
+    assert_eq!(Message::from_json(json.as_bytes()).unwrap(), message());
+}
+
+#[cfg(feature = "camel-case")]
+#[test]
+fn camel_case_fields_are_serialized() {
+    let json = message().to_json().unwrap();
+
+    assert!(json.contains(r#""customerId":1042"#), "{json}");
+    assert!(json.contains(r#""consumptionMwh":6.5"#), "{json}");
+}
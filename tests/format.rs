@@ -0,0 +1,77 @@
+use h4_bigdata::{Message, MilliwattHours};
+
+fn message() -> Message {
+    Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000)
+}
+
+#[test]
+fn bincode_round_trips() {
+    let bytes = message().to_bincode().unwrap();
+
+    assert_eq!(Message::from_bincode(&bytes).unwrap(), message());
+}
+
+#[test]
+fn msgpack_round_trips() {
+    let bytes = message().to_msgpack().unwrap();
+
+    assert_eq!(Message::from_msgpack(&bytes).unwrap(), message());
+}
+
+#[test]
+fn msgpack_is_smaller_than_json() {
+    let msgpack = message().to_msgpack().unwrap();
+    let json = message().to_json().unwrap();
+
+    assert!(msgpack.len() < json.len(), "{msgpack:?} vs {json}");
+}
+
+#[test]
+fn msgpack_encodes_consumption_as_a_single_float() {
+    let bytes = message().to_msgpack().unwrap();
+    let float32 = [[0xca].as_slice(), &6.5_f32.to_be_bytes()].concat();
+
+    assert!(bytes.windows(float32.len()).any(|x| x == float32));
+}
+
+#[test]
+fn protobuf_round_trips() {
+    let bytes = message().to_protobuf().unwrap();
+
+    assert_eq!(Message::from_protobuf(&bytes).unwrap(), message());
+}
+
+#[test]
+fn protobuf_rejects_timestamps_beyond_a_uint64() {
+    let message = Message::new(1042, MilliwattHours(6.5), u128::from(u64::MAX) + 1);
+
+    assert!(message.to_protobuf().is_err());
+}
+
+#[test]
+fn protobuf_rejects_invalid_bytes() {
+    assert!(Message::from_protobuf(&[0xff, 0xff, 0xff]).is_err());
+}
+
+#[test]
+fn size_hint_is_close_to_the_json_length() {
+    for message in [
+        message(),
+        Message::new(1000, MilliwattHours(0.0), 0),
+        Message::new(9999, MilliwattHours(1.234_567_9e-7), u128::from(u64::MAX)),
+    ] {
+        let hint = message.size_hint();
+        let len = message.to_json().unwrap().len();
+
+        assert!(hint >= len / 2 && hint <= len * 2, "{hint} vs {len}");
+    }
+}
+
+#[test]
+fn json_buffer_is_overwritten() {
+    let mut buf = String::from("stale");
+    message().write_json_into(&mut buf).unwrap();
+    message().write_json_into(&mut buf).unwrap();
+
+    assert_eq!(buf, message().to_json().unwrap());
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    slice,
+    time::Duration,
+};
+
+use h4_bigdata::{
+    admin,
+    clock::SystemClock,
+    format::Format,
+    partition,
+    producer::{self, ClusterMetadata, MessageProducer},
+    Message,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use rdkafka::{
+    consumer::{Consumer, StreamConsumer},
+    ClientConfig, Message as _,
+};
+use testcontainers_modules::{
+    kafka::{Kafka, KAFKA_PORT},
+    testcontainers::runners::AsyncRunner,
+};
+use tokio::time;
+
+const TOPIC: &str = "household_consumption";
+const MESSAGES: usize = 100;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::test]
+async fn produced_messages_are_consumed() {
+    let kafka = Kafka::default().start().await.unwrap();
+    let port = kafka.get_host_port_ipv4(KAFKA_PORT).await.unwrap();
+    let brokers = format!("127.0.0.1:{port}");
+
+    admin::create_topics(
+        &brokers,
+        &HashMap::new(),
+        &[TOPIC.to_string()],
+        Some(1),
+        Some(1),
+        TIMEOUT,
+    )
+    .await
+    .unwrap();
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let messages = Message::batch_with_rng(&mut rng, &SystemClock, MESSAGES).unwrap();
+    let producer = MessageProducer::new(slice::from_ref(&brokers), TOPIC, &HashMap::new()).unwrap();
+    for result in producer.send_batch(&messages).await {
+        result.unwrap();
+    }
+
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &brokers)
+        .set("group.id", "h4-bigdata-test")
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .unwrap();
+    consumer.subscribe(&[TOPIC]).unwrap();
+
+    let mut consumed = BTreeSet::new();
+    for _ in 0..MESSAGES {
+        let record = time::timeout(TIMEOUT, consumer.recv())
+            .await
+            .expect("Timed out waiting for a message!")
+            .unwrap();
+        let message = Format::Json.decode(record.payload().unwrap()).unwrap();
+
+        consumed.insert(message.customer_id());
+    }
+
+    let produced: BTreeSet<_> = messages.iter().map(Message::customer_id).collect();
+    assert_eq!(consumed, produced);
+}
+
+#[tokio::test]
+async fn cluster_metadata_is_fetched() {
+    let kafka = Kafka::default().start().await.unwrap();
+    let port = kafka.get_host_port_ipv4(KAFKA_PORT).await.unwrap();
+    let brokers = format!("127.0.0.1:{port}");
+
+    admin::create_topics(
+        &brokers,
+        &HashMap::new(),
+        &[TOPIC.to_string()],
+        Some(3),
+        Some(1),
+        TIMEOUT,
+    )
+    .await
+    .unwrap();
+
+    let topics = [TOPIC.to_string()];
+    let (_, metadata) =
+        producer::create_producer_with_metadata(&brokers, &HashMap::new(), &topics, TIMEOUT)
+            .unwrap();
+
+    assert_eq!(
+        metadata,
+        ClusterMetadata {
+            brokers: 1,
+            partitions: Some(3),
+        },
+    );
+}
+
+#[tokio::test]
+async fn fewest_partitions_are_counted() {
+    let kafka = Kafka::default().start().await.unwrap();
+    let port = kafka.get_host_port_ipv4(KAFKA_PORT).await.unwrap();
+    let brokers = format!("127.0.0.1:{port}");
+    let topics = [format!("{TOPIC}_0"), format!("{TOPIC}_1")];
+    let count = || {
+        let (_, metadata) =
+            producer::create_producer_with_metadata(&brokers, &HashMap::new(), &topics, TIMEOUT)
+                .unwrap();
+
+        metadata.partitions
+    };
+
+    assert_eq!(count(), None);
+
+    for (topic, partitions) in topics.iter().zip([4, 2]) {
+        admin::create_topics(
+            &brokers,
+            &HashMap::new(),
+            slice::from_ref(topic),
+            Some(partitions),
+            Some(1),
+            TIMEOUT,
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(count(), Some(2));
+}
+
+#[tokio::test]
+async fn tombstones_have_a_key_and_no_payload() {
+    let kafka = Kafka::default().start().await.unwrap();
+    let port = kafka.get_host_port_ipv4(KAFKA_PORT).await.unwrap();
+    let brokers = format!("127.0.0.1:{port}");
+
+    admin::create_topics(
+        &brokers,
+        &HashMap::new(),
+        &[TOPIC.to_string()],
+        Some(3),
+        Some(1),
+        TIMEOUT,
+    )
+    .await
+    .unwrap();
+
+    let producer = MessageProducer::new(slice::from_ref(&brokers), TOPIC, &HashMap::new())
+        .unwrap()
+        .with_partitions(Some(3));
+    let delivery = producer.send_tombstone(1234).await.unwrap();
+    assert_eq!(delivery.partition, partition::partition_for(1234, 3));
+
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &brokers)
+        .set("group.id", "h4-bigdata-test")
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .unwrap();
+    consumer.subscribe(&[TOPIC]).unwrap();
+
+    let record = time::timeout(TIMEOUT, consumer.recv())
+        .await
+        .expect("Timed out waiting for the tombstone!")
+        .unwrap();
+
+    assert_eq!(record.key(), Some(b"1234".as_slice()));
+    assert_eq!(record.payload(), None);
+}
@@ -0,0 +1,45 @@
+use h4_bigdata::config::{Config, Keying};
+
+const STICKY_LINGER: &str = "sticky.partitioning.linger.ms";
+
+#[test]
+fn keyed_leaves_partitioning_to_the_client() {
+    let config = Config::default();
+
+    assert!(config.keying.is_keyed());
+    assert!(!config.client_properties().contains_key(STICKY_LINGER));
+}
+
+#[test]
+fn unkeyed_picks_a_partition_per_message() {
+    let config = Config {
+        keying: Keying::None,
+        ..Config::default()
+    };
+
+    assert_eq!(config.client_properties()[STICKY_LINGER], "0");
+}
+
+#[test]
+fn kafka_config_overrides_keying() {
+    let mut config = Config {
+        keying: Keying::Sticky,
+        ..Config::default()
+    };
+    config
+        .kafka_config
+        .insert(STICKY_LINGER.to_string(), "50".to_string());
+
+    assert_eq!(config.client_properties()[STICKY_LINGER], "50");
+}
+
+#[test]
+fn partitions_require_keys() {
+    let config = Config {
+        keying: Keying::Sticky,
+        partitions: Some(3),
+        ..Config::default()
+    };
+
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use h4_bigdata::{
+    config::{Config, Keying},
+    manifest::Manifest,
+    model::ConsumptionModel,
+    ramp::RateRamp,
+};
+
+/// A configuration differing from the defaults in values needing special formatting.
+fn config() -> Config {
+    let mut config = Config {
+        ramp: Some(RateRamp::new(100.0, 1_000.0, Duration::from_secs(60))),
+        customer_range: 1_000..=1_099,
+        model: Some(ConsumptionModel::Normal {
+            mean: 5.0,
+            std: 1.5,
+        }),
+        keying: Keying::Sticky,
+        sasl_password: Some("hunter2".to_string()),
+        ..Config::default()
+    };
+    config
+        .kafka_config
+        .insert("linger.ms".to_string(), "5".to_string());
+    config
+        .kafka_config
+        .insert("ssl.key.password".to_string(), "hunter2".to_string());
+
+    config
+}
+
+#[test]
+fn config_is_reproduced() {
+    let manifest = Manifest::new(&config(), 42, 1_700_000_000_000).unwrap();
+    let json = serde_json::to_string(&manifest).unwrap();
+    let reproduced = serde_json::from_str::<Manifest>(&json)
+        .unwrap()
+        .into_config()
+        .unwrap();
+
+    assert_eq!(reproduced.seed, Some(42));
+    assert_eq!(reproduced.ramp, config().ramp);
+    assert_eq!(reproduced.customer_range, 1_000..=1_099);
+    assert_eq!(reproduced.model, config().model);
+    assert_eq!(reproduced.keying, Keying::Sticky);
+    assert_eq!(reproduced.kafka_config["linger.ms"], "5");
+}
+
+#[test]
+fn credentials_are_left_out() {
+    let manifest = Manifest::new(&config(), 42, 1_700_000_000_000).unwrap();
+    let json = serde_json::to_string(&manifest).unwrap();
+
+    assert!(!json.contains("hunter2"));
+}
@@ -0,0 +1,34 @@
+use h4_bigdata::{
+    config::DEFAULT_MAX_BYTES, error::ProducerError, format::Format, producer, Message,
+    MilliwattHours,
+};
+
+#[test]
+fn message_fits_the_default_limit() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000);
+    let payload = Format::Json.encode(&message).unwrap();
+
+    assert!(producer::check_payload_size(&payload, DEFAULT_MAX_BYTES).is_ok());
+}
+
+#[test]
+fn oversized_payload_is_rejected() {
+    let payload = vec![0; DEFAULT_MAX_BYTES + 1];
+
+    assert!(matches!(
+        producer::check_payload_size(&payload, DEFAULT_MAX_BYTES),
+        Err(ProducerError::PayloadTooLarge {
+            size,
+            limit: DEFAULT_MAX_BYTES,
+        }) if size == DEFAULT_MAX_BYTES + 1,
+    ));
+}
+
+#[test]
+fn payload_at_the_limit_is_accepted() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000);
+    let payload = Format::Json.encode(&message).unwrap();
+
+    assert!(producer::check_payload_size(&payload, payload.len()).is_ok());
+    assert!(producer::check_payload_size(&payload, payload.len() - 1).is_err());
+}
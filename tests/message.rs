@@ -0,0 +1,146 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use h4_bigdata::{
+    clock::FixedClock, error::MessageError, Message, MilliwattHours, CUSTOMER_ID_RANGE,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn nan_consumption_is_rejected() {
+    assert!(matches!(
+        Message::try_new(1042, MilliwattHours(f32::NAN), 0),
+        Err(MessageError::NonFiniteConsumption(x)) if x.is_nan(),
+    ));
+}
+
+#[test]
+fn infinite_consumption_is_rejected() {
+    for consumption in [f32::INFINITY, f32::NEG_INFINITY] {
+        assert!(matches!(
+            Message::try_new(1042, MilliwattHours(consumption), 0),
+            Err(MessageError::NonFiniteConsumption(x)) if x == consumption,
+        ));
+    }
+}
+
+#[test]
+fn negative_consumption_is_rejected() {
+    assert!(matches!(
+        Message::try_new(1042, MilliwattHours(-0.5), 0),
+        Err(MessageError::NegativeConsumption(x)) if x == -0.5,
+    ));
+}
+
+#[test]
+fn zero_customer_id_is_rejected() {
+    assert!(matches!(
+        Message::try_new(0, MilliwattHours(1.0), 0),
+        Err(MessageError::ZeroCustomerId),
+    ));
+}
+
+#[test]
+fn valid_message_is_accepted() {
+    assert_eq!(
+        Message::try_new(1042, MilliwattHours(0.0), 0).unwrap(),
+        Message::new(1042, MilliwattHours(0.0), 0),
+    );
+}
+
+#[test]
+fn same_seed_generates_the_same_messages() {
+    let generate = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        Message::batch_with_rng(&mut rng, &FixedClock(1_700_000_000_000), 100)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.customer_id(), x.consumption()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(generate(42), generate(42));
+    assert_ne!(generate(42), generate(43));
+}
+
+#[test]
+fn builder_timestamps_with_the_current_time() {
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    };
+
+    let before = now();
+    let message = Message::builder()
+        .customer_id(1042)
+        .consumption(MilliwattHours(6.5))
+        .build()
+        .unwrap();
+    let after = now();
+
+    assert_eq!(message.customer_id(), 1042);
+    assert_eq!(message.consumption(), MilliwattHours(6.5));
+    assert!((before..=after).contains(&message.timestamp()));
+}
+
+#[test]
+fn builder_requires_every_other_field() {
+    assert!(matches!(
+        Message::builder().consumption(MilliwattHours(1.0)).build(),
+        Err(MessageError::MissingField("customer_id")),
+    ));
+    assert!(matches!(
+        Message::builder().customer_id(1042).build(),
+        Err(MessageError::MissingField("consumption")),
+    ));
+}
+
+#[test]
+fn customer_ids_must_have_four_digits() {
+    let validate =
+        |customer_id| Message::new(customer_id, MilliwattHours(1.0), 0).validate_customer_id();
+
+    assert_eq!(validate(999), Err(MessageError::CustomerIdOutOfRange(999)));
+    assert_eq!(validate(1_000), Ok(()));
+    assert_eq!(validate(9_999), Ok(()));
+    assert_eq!(
+        validate(10_000),
+        Err(MessageError::CustomerIdOutOfRange(10_000))
+    );
+    assert_eq!(CUSTOMER_ID_RANGE, 1_000..=9_999);
+}
+
+#[test]
+fn consumption_is_generated_below_the_max() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let clock = FixedClock(1_700_000_000_000);
+    for _ in 0..1_000 {
+        let in_range =
+            Message::with_rng_in_range(&mut rng, &clock, CUSTOMER_ID_RANGE, 100.0).unwrap();
+        let in_window = Message::with_rng_in_window(&mut rng, 0, 1_000, 100.0).unwrap();
+
+        for message in [in_range, in_window] {
+            assert!((0.0..100.0).contains(&message.consumption().value()));
+        }
+    }
+}
+
+#[test]
+fn key_is_the_decimal_customer_id() {
+    assert_eq!(Message::new(1042, MilliwattHours(1.0), 0).key(), "1042");
+    assert_eq!(Message::new(9999, MilliwattHours(1.0), 0).key(), "9999");
+}
+
+#[test]
+fn timestamps_are_plausible_up_to_the_max_skew() {
+    let now = 1_700_000_000_000;
+    let max_skew = 5_000;
+    let message = |timestamp| Message::new(1042, MilliwattHours(1.0), timestamp);
+
+    assert!(message(now + max_skew).is_plausible(now, max_skew));
+    assert!(!message(now + max_skew + 1).is_plausible(now, max_skew));
+    assert!(message(now).is_plausible(now, max_skew));
+    assert!(message(0).is_plausible(now, max_skew));
+}
@@ -0,0 +1,25 @@
+use h4_bigdata::partition::topic_for;
+
+const TOPIC: &str = "consumption";
+
+#[test]
+fn single_shard_keeps_the_base_topic() {
+    assert_eq!(topic_for(TOPIC, 1042, 1), TOPIC);
+}
+
+#[test]
+fn customers_are_sharded_by_modulo() {
+    assert_eq!(topic_for(TOPIC, 1040, 10), "consumption_0");
+    assert_eq!(topic_for(TOPIC, 1042, 10), "consumption_2");
+    assert_eq!(topic_for(TOPIC, 9_999, 10), "consumption_9");
+}
+
+#[test]
+fn topic_assignment_is_consistent() {
+    for customer_id in 1_000..2_000 {
+        assert_eq!(
+            topic_for(TOPIC, customer_id, 7),
+            topic_for(TOPIC, customer_id, 7),
+        );
+    }
+}
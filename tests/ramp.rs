@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use h4_bigdata::ramp::RateRamp;
+
+#[test]
+fn rate_is_interpolated_during_the_ramp() {
+    let ramp = RateRamp::new(100.0, 1_100.0, Duration::from_secs(10));
+
+    assert_eq!(ramp.rate_at(Duration::ZERO), 100.0);
+    assert_eq!(ramp.rate_at(Duration::from_secs(5)), 600.0);
+    assert_eq!(ramp.rate_at(Duration::from_millis(7_500)), 850.0);
+}
+
+#[test]
+fn rate_holds_after_the_ramp() {
+    let ramp = RateRamp::new(100.0, 1_100.0, Duration::from_secs(10));
+
+    assert_eq!(ramp.rate_at(Duration::from_secs(10)), 1_100.0);
+    assert_eq!(ramp.rate_at(Duration::from_secs(3_600)), 1_100.0);
+    assert_eq!(RateRamp::constant(42.0).rate_at(Duration::ZERO), 42.0,);
+}
@@ -0,0 +1,73 @@
+use clap::ValueEnum;
+use h4_bigdata::{error::ParseMessageError, format::Format, Message, MilliwattHours};
+use proptest::prelude::*;
+
+/// Generate any message that can be encoded, with a finite, non-negative consumption and a
+/// timestamp that fits in an `i64`.
+fn message() -> impl Strategy<Value = Message> {
+    (any::<u32>(), 0.0_f32..=f32::MAX, 0..=i64::MAX as u128).prop_map(
+        |(customer_id, consumption, timestamp)| {
+            Message::new(customer_id, MilliwattHours(consumption), timestamp)
+        },
+    )
+}
+
+/// Check that two messages are equal, allowing for the rounding of their consumptions.
+fn roughly_equal(a: &Message, b: &Message) -> bool {
+    a.approx_eq(b, a.consumption().value() * f32::EPSILON)
+}
+
+proptest! {
+    #[test]
+    fn json_round_trips(message in message()) {
+        let json = message.to_json().unwrap();
+        let decoded = Message::from_json(json.as_bytes()).unwrap();
+
+        prop_assert!(roughly_equal(&message, &decoded), "{message:?} became {decoded:?}");
+    }
+
+    #[test]
+    fn string_form_round_trips(message in message()) {
+        let decoded: Message = message.to_string().parse().unwrap();
+
+        prop_assert_eq!(message, decoded);
+    }
+
+    #[test]
+    fn every_format_round_trips(message in message()) {
+        for format in Format::value_variants() {
+            // Avro only has signed integers, so larger IDs are rejected rather than truncated.
+            if *format == Format::Avro && i32::try_from(message.customer_id()).is_err() {
+                prop_assert!(format.encode(&message).is_err());
+                continue;
+            }
+
+            let bytes = format.encode(&message).unwrap();
+            let decoded = format.decode(&bytes).unwrap();
+
+            prop_assert!(
+                roughly_equal(&message, &decoded),
+                "{format:?}: {message:?} became {decoded:?}",
+            );
+        }
+    }
+}
+
+#[test]
+fn string_form_rejects_malformed_input() {
+    assert_eq!(
+        "customer=1042 consumption=6.5mWh".parse::<Message>(),
+        Err(ParseMessageError::MissingField("ts")),
+    );
+    assert_eq!(
+        "customer=1042 consumption=6.5 ts=1700000000000".parse::<Message>(),
+        Err(ParseMessageError::InvalidValue {
+            field: "consumption",
+            value: "6.5".to_string(),
+        }),
+    );
+    assert_eq!(
+        "customer=1042 customer=1043".parse::<Message>(),
+        Err(ParseMessageError::DuplicateField("customer")),
+    );
+}
@@ -0,0 +1,30 @@
+use h4_bigdata::{error::MessageError, sanitize::Sanitize, Message, MilliwattHours};
+
+#[test]
+fn zero_replaces_nan_consumption() {
+    let message = Message::new(1042, MilliwattHours(f32::NAN), 1_700_000_000_000);
+    let sanitized = Sanitize::Zero.apply(message).unwrap();
+
+    assert_eq!(
+        sanitized,
+        Message::new(1042, MilliwattHours(0.0), 1_700_000_000_000)
+    );
+    assert!(!sanitized.to_json().unwrap().contains("null"));
+}
+
+#[test]
+fn reject_drops_infinite_consumption() {
+    let message = Message::new(1042, MilliwattHours(f32::INFINITY), 1_700_000_000_000);
+
+    assert_eq!(
+        Sanitize::Reject.apply(message),
+        Err(MessageError::NonFiniteConsumption(f32::INFINITY)),
+    );
+}
+
+#[test]
+fn finite_consumption_is_kept() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000);
+
+    assert_eq!(Sanitize::Reject.apply(message.clone()), Ok(message));
+}
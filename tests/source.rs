@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use h4_bigdata::{
+    error::SourceError,
+    model::ConsumptionModel,
+    source::{CustomerMode, MessageSource, Pacer, RandomSource},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Pick the customers of `n` messages generated in a customer mode.
+fn customers(mode: CustomerMode, n: usize) -> Vec<u32> {
+    let model = ConsumptionModel::Uniform { min: 0.0, max: 1.0 };
+    let mut source = RandomSource::new(StdRng::seed_from_u64(42), model, 1_000..=1_099)
+        .unwrap()
+        .with_customer_mode(mode);
+
+    (0..n)
+        .map(|_| source.next().unwrap().customer_id())
+        .collect()
+}
+
+#[test]
+fn sequential_mode_cycles_in_order() {
+    let expected: Vec<u32> = (1_000..=1_099).chain(1_000..=1_009).collect();
+
+    assert_eq!(customers(CustomerMode::Sequential, 110), expected);
+}
+
+#[test]
+fn hashed_mode_visits_every_customer_once_per_cycle() {
+    let picked = customers(CustomerMode::Hashed, 200);
+    let (first, second) = picked.split_at(100);
+
+    let unique: HashSet<_> = first.iter().collect();
+    assert_eq!(unique.len(), 100);
+    assert!(first.iter().all(|x| (1_000..=1_099).contains(x)));
+    assert_eq!(first, second);
+    assert_ne!(first, customers(CustomerMode::Sequential, 100));
+}
+
+#[test]
+fn invalid_skew_is_an_error() {
+    let model = ConsumptionModel::Uniform { min: 0.0, max: 1.0 };
+    for alpha in [-1.0, f64::NAN, f64::INFINITY] {
+        let source = RandomSource::new(StdRng::seed_from_u64(42), model, 1_000..=1_099).unwrap();
+
+        assert!(matches!(
+            source.with_customer_skew(alpha),
+            Err(SourceError::InvalidSkew(_)),
+        ));
+    }
+}
+
+#[test]
+fn invalid_time_scale_is_an_error() {
+    for time_scale in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            Pacer::new(time_scale),
+            Err(SourceError::InvalidTimeScale(_)),
+        ));
+    }
+
+    assert!(Pacer::new(2.0).is_ok());
+}
@@ -0,0 +1,42 @@
+use futures_util::StreamExt;
+use h4_bigdata::{
+    model::ConsumptionModel,
+    source::{MessageSource, RandomSource},
+    stream::{self, SourceStream},
+    Message, CUSTOMER_ID_RANGE,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const MODEL: ConsumptionModel = ConsumptionModel::Uniform { min: 0.0, max: 1.0 };
+
+#[tokio::test]
+async fn stream_composes_with_operators() {
+    let messages: Vec<Message> = stream::message_stream(StdRng::seed_from_u64(42), MODEL)
+        .unwrap()
+        .filter(|x| std::future::ready(x.consumption().value() < 0.5))
+        .take(10)
+        .collect()
+        .await;
+
+    assert_eq!(messages.len(), 10);
+    assert!(messages
+        .iter()
+        .all(|x| CUSTOMER_ID_RANGE.contains(&x.customer_id()) && x.consumption().value() < 0.5));
+}
+
+#[tokio::test]
+async fn stream_matches_its_source() {
+    let mut source = RandomSource::new(StdRng::seed_from_u64(42), MODEL, 1_000..=1_099).unwrap();
+    let expected: Vec<u32> = (0..5)
+        .map(|_| source.next().unwrap().customer_id())
+        .collect();
+
+    let source = RandomSource::new(StdRng::seed_from_u64(42), MODEL, 1_000..=1_099).unwrap();
+    let streamed: Vec<u32> = SourceStream::new(source)
+        .map(|x| x.customer_id())
+        .take(5)
+        .collect()
+        .await;
+
+    assert_eq!(streamed, expected);
+}
@@ -0,0 +1,50 @@
+use h4_bigdata::{timestamp::TimestampUnit, Message, MilliwattHours};
+
+#[test]
+fn millis_are_kept() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_123);
+
+    assert_eq!(TimestampUnit::Millis.apply(message.clone()), message);
+}
+
+#[test]
+fn seconds_are_rounded_down() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_999);
+
+    assert_eq!(
+        TimestampUnit::Seconds.apply(message).timestamp(),
+        1_700_000_000
+    );
+}
+
+#[test]
+fn micros_are_scaled_up() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_123);
+
+    assert_eq!(
+        TimestampUnit::Micros.apply(message).timestamp(),
+        1_700_000_000_123_000,
+    );
+}
+
+#[test]
+fn revert_is_inverse_of_apply() {
+    let message = Message::new(1042, MilliwattHours(6.5), 1_700_000_000_000);
+
+    for unit in [
+        TimestampUnit::Seconds,
+        TimestampUnit::Millis,
+        TimestampUnit::Micros,
+    ] {
+        assert_eq!(unit.revert(unit.apply(message.clone())), message);
+    }
+}
+
+#[test]
+fn symbols_are_parsed() {
+    assert_eq!(
+        TimestampUnit::from_symbol(b"us"),
+        Some(TimestampUnit::Micros)
+    );
+    assert_eq!(TimestampUnit::from_symbol(b"ns"), None);
+}
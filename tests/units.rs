@@ -0,0 +1,172 @@
+use h4_bigdata::{
+    units::{KilowattHours, WattHours},
+    MilliwattHours,
+};
+
+#[test]
+fn watt_hours_convert_by_a_thousand() {
+    assert_eq!(
+        MilliwattHours::from(WattHours(2.5)),
+        MilliwattHours(2_500.0)
+    );
+    assert_eq!(WattHours::from(MilliwattHours(2_500.0)), WattHours(2.5));
+}
+
+#[test]
+fn kilowatt_hours_convert_by_a_million() {
+    assert_eq!(
+        MilliwattHours::from(KilowattHours(1.5)),
+        MilliwattHours(1_500_000.0),
+    );
+    assert_eq!(
+        KilowattHours::from(MilliwattHours(1_500_000.0)),
+        KilowattHours(1.5),
+    );
+}
+
+#[test]
+fn kilowatt_hours_convert_to_watt_hours_by_a_thousand() {
+    assert_eq!(WattHours::from(KilowattHours(0.25)), WattHours(250.0));
+    assert_eq!(KilowattHours::from(WattHours(250.0)), KilowattHours(0.25));
+}
+
+#[test]
+fn conversions_round_trip() {
+    let consumption = MilliwattHours(6_500.0);
+    let kilowatt_hours: KilowattHours = consumption.into();
+    let watt_hours: WattHours = kilowatt_hours.into();
+
+    assert_eq!(MilliwattHours::from(watt_hours), consumption);
+}
+
+#[test]
+fn from_kilowatt_hours_scales_by_a_million() {
+    assert_eq!(MilliwattHours::from_kilowatt_hours(1.0).0, 1_000_000.0);
+    assert_eq!(MilliwattHours::from_watt_hours(1.0).0, 1_000.0);
+}
+
+#[test]
+fn unit_conversions_round_trip_within_epsilon() {
+    for value in [0.0, 0.001, 1.0, 6.5, 1_234.5] {
+        let kilowatt_hours = MilliwattHours::from_kilowatt_hours(value).to_kilowatt_hours();
+        let watt_hours = MilliwattHours::from_watt_hours(value).to_watt_hours();
+
+        assert!((kilowatt_hours - value).abs() <= value * f32::EPSILON);
+        assert!((watt_hours - value).abs() <= value * f32::EPSILON);
+    }
+}
+
+#[test]
+fn milliwatt_hours_display_their_unit() {
+    assert_eq!(MilliwattHours(1_234.5).to_string(), "1234.5 mWh");
+}
+
+#[test]
+fn consumptions_sum() {
+    let consumptions = vec![
+        MilliwattHours(1.5),
+        MilliwattHours(2.0),
+        MilliwattHours(0.5),
+    ];
+
+    assert_eq!(
+        consumptions.iter().sum::<MilliwattHours>(),
+        MilliwattHours(4.0)
+    );
+    assert_eq!(
+        consumptions.into_iter().sum::<MilliwattHours>(),
+        MilliwattHours(4.0),
+    );
+    assert_eq!(
+        Vec::<MilliwattHours>::new()
+            .into_iter()
+            .sum::<MilliwattHours>(),
+        MilliwattHours(0.0),
+    );
+}
+
+#[test]
+fn addition_is_associative_within_tolerance() {
+    let (a, b, c) = (
+        MilliwattHours(0.1),
+        MilliwattHours(0.2),
+        MilliwattHours(0.3),
+    );
+    let left = (a + b) + c;
+    let right = a + (b + c);
+
+    assert!((left.0 - right.0).abs() <= left.0 * f32::EPSILON);
+}
+
+#[test]
+fn arithmetic_applies_to_the_inner_value() {
+    let mut total = MilliwattHours(2.0);
+    total += MilliwattHours(1.0);
+
+    assert_eq!(total, MilliwattHours(3.0));
+    assert_eq!(total - MilliwattHours(0.5), MilliwattHours(2.5));
+    assert_eq!(total * 2.0, MilliwattHours(6.0));
+}
+
+#[test]
+fn f32_conversions_round_trip() {
+    let consumption: MilliwattHours = 6.5.into();
+
+    assert_eq!(consumption, MilliwattHours(6.5));
+    assert_eq!(consumption.value(), 6.5);
+    assert_eq!(f32::from(consumption), 6.5);
+    assert_eq!(MilliwattHours::from(f32::from(consumption)), consumption);
+}
+
+#[test]
+fn clamp_keeps_the_bounds() {
+    let (min, max) = (MilliwattHours(0.0), MilliwattHours(10.0));
+
+    assert_eq!(MilliwattHours(-1.0).clamp(min, max), min);
+    assert_eq!(MilliwattHours(0.0).clamp(min, max), min);
+    assert_eq!(MilliwattHours(5.0).clamp(min, max), MilliwattHours(5.0));
+    assert_eq!(MilliwattHours(10.0).clamp(min, max), max);
+    assert_eq!(MilliwattHours(f32::INFINITY).clamp(min, max), max);
+}
+
+#[test]
+#[should_panic]
+fn clamp_panics_on_inverted_bounds() {
+    let _ = MilliwattHours(5.0).clamp(MilliwattHours(10.0), MilliwattHours(0.0));
+}
+
+#[test]
+fn saturating_add_stays_finite() {
+    let max = MilliwattHours(f32::MAX);
+
+    assert_eq!(max.saturating_add(max), max);
+    assert_eq!(
+        MilliwattHours(f32::MIN).saturating_add(MilliwattHours(f32::MIN)),
+        MilliwattHours(f32::MIN),
+    );
+    assert_eq!(
+        MilliwattHours(1.5).saturating_add(MilliwattHours(2.5)),
+        MilliwattHours(4.0)
+    );
+}
+
+#[test]
+fn nan_propagates() {
+    let nan = MilliwattHours(f32::NAN);
+
+    assert!(nan
+        .clamp(MilliwattHours(0.0), MilliwattHours(10.0))
+        .value()
+        .is_nan());
+    assert!(nan.saturating_add(MilliwattHours(1.0)).value().is_nan());
+    assert!(MilliwattHours(1.0).saturating_add(nan).value().is_nan());
+    assert!(!nan.is_valid());
+}
+
+#[test]
+fn only_finite_non_negative_energy_is_valid() {
+    assert!(MilliwattHours(0.0).is_valid());
+    assert!(MilliwattHours(f32::MAX).is_valid());
+    assert!(!MilliwattHours(-0.1).is_valid());
+    assert!(!MilliwattHours(f32::INFINITY).is_valid());
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use h4_bigdata::{
+    aggregate::{PendingOffsets, WindowedAggregator},
+    Message, MilliwattHours,
+};
+
+const MINUTE: u128 = 60_000;
+
+fn windows() -> WindowedAggregator {
+    WindowedAggregator::new(Duration::from_secs(60), Duration::from_secs(10))
+}
+
+fn message(customer_id: u32, consumption: f32, timestamp: u128) -> Message {
+    Message::new(customer_id, MilliwattHours(consumption), timestamp)
+}
+
+#[test]
+fn windows_close_after_the_grace_period() {
+    let mut windows = windows();
+    assert!(windows.record(&message(1, 1.0, 1_000)));
+    assert!(windows.record(&message(1, 2.0, 59_000)));
+    assert!(windows.record(&message(2, 4.0, 30_000)));
+    assert!(windows.record(&message(1, 8.0, MINUTE + 5_000)));
+
+    // The first window ended, but is still within its grace period.
+    assert!(windows.flush().is_empty());
+
+    assert!(windows.record(&message(2, 16.0, MINUTE + 10_000)));
+    assert_eq!(
+        windows.flush(),
+        vec![message(1, 3.0, 0), message(2, 4.0, 0)],
+    );
+    assert_eq!(
+        windows.drain(),
+        vec![message(1, 8.0, MINUTE), message(2, 16.0, MINUTE)],
+    );
+}
+
+#[test]
+fn late_messages_within_the_grace_period_are_recorded() {
+    let mut windows = windows();
+    assert!(windows.record(&message(1, 1.0, MINUTE + 9_000)));
+    assert!(windows.record(&message(1, 2.0, 50_000)));
+
+    assert!(windows.flush().is_empty());
+    assert_eq!(windows.late(), 0);
+    assert_eq!(
+        windows.drain(),
+        vec![message(1, 2.0, 0), message(1, 1.0, MINUTE)],
+    );
+}
+
+#[test]
+fn late_messages_after_the_grace_period_are_dropped() {
+    let mut windows = windows();
+    assert!(windows.record(&message(1, 1.0, 2 * MINUTE)));
+    assert!(!windows.record(&message(1, 2.0, 50_000)));
+
+    assert_eq!(windows.late(), 1);
+    assert_eq!(windows.drain(), vec![message(1, 1.0, 2 * MINUTE)]);
+}
+
+#[test]
+fn window_starts_are_aligned() {
+    let windows = windows();
+
+    assert_eq!(windows.window_start(0), 0);
+    assert_eq!(windows.window_start(MINUTE - 1), 0);
+    assert_eq!(windows.window_start(MINUTE), MINUTE);
+}
+
+#[test]
+fn offsets_are_held_back_until_their_window_is_delivered() {
+    let mut offsets = PendingOffsets::default();
+    offsets.record(0, 10, Some(0));
+    offsets.record(0, 11, Some(MINUTE));
+    offsets.record(1, 5, None);
+
+    assert_eq!(offsets.committable(), vec![(0, 10), (1, 6)]);
+    assert!(offsets.committable().is_empty());
+
+    offsets.deliver(0);
+    assert_eq!(offsets.committable(), vec![(0, 11)]);
+
+    offsets.record(0, 12, None);
+    assert!(offsets.committable().is_empty());
+
+    offsets.deliver(MINUTE);
+    assert_eq!(offsets.committable(), vec![(0, 13)]);
+}
+
+#[test]
+fn offsets_of_undelivered_windows_are_never_committed() {
+    let mut offsets = PendingOffsets::default();
+    offsets.record(0, 10, Some(0));
+    offsets.record(0, 11, Some(MINUTE));
+
+    offsets.deliver(MINUTE);
+    assert_eq!(offsets.committable(), vec![(0, 10)]);
+}
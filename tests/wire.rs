@@ -0,0 +1,24 @@
+use h4_bigdata::{format::Format, Message, MilliwattHours};
+
+/// The JSON external consumers decode, which must only change along with the schema version.
+#[cfg(not(feature = "camel-case"))]
+const JSON: &str = r#"{"customer_id":1234,"consumption":5.5,"timestamp":1700000000000}"#;
+
+/// The JSON external consumers decode, which must only change along with the schema version.
+#[cfg(feature = "camel-case")]
+const JSON: &str = r#"{"customerId":1234,"consumptionMwh":5.5,"timestamp":1700000000000}"#;
+
+fn message() -> Message {
+    Message::new(1234, MilliwattHours(5.5), 1_700_000_000_000)
+}
+
+#[test]
+fn json_wire_format_is_stable() {
+    assert_eq!(message().to_json().unwrap(), JSON);
+    assert_eq!(Format::Json.encode(&message()).unwrap(), JSON.as_bytes());
+}
+
+#[test]
+fn json_wire_format_decodes() {
+    assert_eq!(Message::from_json(JSON.as_bytes()).unwrap(), message());
+}